@@ -0,0 +1,93 @@
+use std::{env, process};
+
+use arche::game::arena::{Arena, ArenaSide};
+
+const USAGE: &str = "Usage: arena [OPTIONS]
+
+Options:
+    --matches <N>        Number of matches to play (default: 100)
+    --player <SPEC>      Strategy of the left archer (default: network)
+    --enemy <SPEC>       Strategy of the right archer (default: network)
+    --noise <F>          Random aim noise added to every shot of both archers (default: 0.02)
+    --seed <N>           Seed of the tower heights, aim noise and random strategies (default: 0)
+    -h, --help           Print this message
+
+A strategy is one of network, solver, random or bracketing. The network strategy
+uses the built-in model unless a model file is given as network:<PATH>.";
+
+struct ArenaOptions {
+    matches: u32,
+    player: ArenaSide,
+    enemy: ArenaSide,
+    noise: f32,
+    seed: u64,
+}
+
+impl ArenaOptions {
+    fn from_args<I: Iterator<Item = String>>(mut args: I) -> Result<Self, String> {
+        let mut options = Self {
+            matches: 100,
+            player: "network".parse()?,
+            enemy: "network".parse()?,
+            noise: 0.02,
+            seed: 0,
+        };
+
+        while let Some(arg) = args.next() {
+            if arg == "-h" || arg == "--help" {
+                println!("{USAGE}");
+                process::exit(0);
+            }
+
+            let value = args
+                .next()
+                .ok_or_else(|| format!("Missing value for '{arg}'"))?;
+            match arg.as_str() {
+                "--matches" => options.matches = parse_value(&arg, &value)?,
+                "--player" => options.player = value.parse()?,
+                "--enemy" => options.enemy = value.parse()?,
+                "--noise" => options.noise = parse_value(&arg, &value)?,
+                "--seed" => options.seed = parse_value(&arg, &value)?,
+                _ => return Err(format!("Unknown option '{arg}'")),
+            }
+        }
+
+        Ok(options)
+    }
+}
+
+fn parse_value<T: std::str::FromStr>(arg: &str, value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("Invalid value '{value}' for '{arg}'"))
+}
+
+fn main() {
+    let options = match ArenaOptions::from_args(env::args().skip(1)) {
+        Ok(options) => options,
+        Err(error) => {
+            eprintln!("{error}\n\n{USAGE}");
+            process::exit(2);
+        }
+    };
+
+    let mut arena = match Arena::new(&options.player, &options.enemy, options.noise, options.seed) {
+        Ok(arena) => arena,
+        Err(error) => {
+            eprintln!("Failed to load a model: {error}");
+            process::exit(1);
+        }
+    };
+
+    println!(
+        "Playing {} matches: {:?} vs {:?}",
+        options.matches, options.player, options.enemy
+    );
+    let report = arena.run(options.matches, |report| {
+        if report.matches % 10 == 0 {
+            println!("Played {}/{} matches", report.matches, options.matches);
+        }
+    });
+
+    print!("{report}");
+}
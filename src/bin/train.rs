@@ -0,0 +1,416 @@
+use std::{
+    env,
+    fs::{self, File, OpenOptions},
+    io::{BufWriter, ErrorKind, Write},
+    path::Path,
+    process,
+};
+
+use arche::game::ai_controls::GENERATIONS;
+use arche::game::ai_model::{AIModel, TrainingMetadata};
+use arche::game::ballistics::{self, LabeledShot};
+use arche::game::genetic_algorithm::{GenerationStats, GeneticAlgorithm, GeneticAlgorithmSettings};
+use arche::game::neural_network::{Activation, MutationNoise, NetworkTopology};
+use arche::game::observation::ObservationFeature;
+
+const DEFAULT_OUTPUT_PATH: &str = "assets/ai/net.model.json";
+const LABEL_GRID_STEPS: usize = 21;
+
+const USAGE: &str = "Usage: train [OPTIONS]
+
+Options:
+    --pop-size <N>             Number of agents in every generation (default: 1024)
+    --inputs <LIST>            Comma separated features the net observes: self_height,
+                               enemy_height, self_health, enemy_health, turn and last_offset
+                               (default: self_height,enemy_height)
+    --hidden <SIZES>           Comma separated hidden layer sizes or 'none' (default: 3)
+    --activations <LIST>       Comma separated activation of every hidden and output layer:
+                               sigmoid, tanh, relu or linear (default: sigmoid everywhere)
+    --learning-rate <F>        Mutation strength applied to weights (default: 0.5)
+    --mutation-rate <F>        Chance of mutating every single weight (default: 1.0)
+    --bias-learning-rate <F>   Mutation strength applied to biases (default: 0.5)
+    --bias-mutation-rate <F>   Chance of mutating every single bias (default: 1.0)
+    --noise <NOISE>            Mutation noise: uniform or gaussian (default: uniform)
+    --generations <N>          Number of generations to run (default: 100)
+    --seed <N>                 Seed of the random number generator (default: 0)
+    --shaping <F>              Weight of the near-miss score, which rewards arrows passing close to
+                               the hitboxes, mixed into the hit score: 0 to 1 (default: 0)
+    --elitism <N>              Best agents copied unchanged into the next generation (default: 2)
+    --selection <METHOD>       Parent selection: roulette, rank or tournament[:SIZE] (default: roulette)
+    --threads <N>              Worker threads used to score agents (default: all cores)
+    --checkpoint <PATH>        Save the whole population to this file while training
+    --checkpoint-every <N>     Generations between checkpoints (default: 10)
+    --resume <PATH>            Continue training from a checkpoint; its training settings
+                               replace the ones given on the command line
+    --metrics <PATH>           Write per generation statistics to a .csv or .jsonl file
+    --labels <PATH>            Write tower heights labelled with the analytical solver's
+                               power and angle to a .csv or .jsonl file
+    --output <PATH>            Where to write the trained net (default: assets/ai/net.model.json)
+    -h, --help                 Print this message";
+
+struct TrainOptions {
+    settings: GeneticAlgorithmSettings,
+    generations: u32,
+    threads: Option<usize>,
+    checkpoint: Option<String>,
+    checkpoint_every: u32,
+    resume: Option<String>,
+    metrics: Option<String>,
+    labels: Option<String>,
+    output: String,
+}
+
+impl TrainOptions {
+    fn from_args<I: Iterator<Item = String>>(mut args: I) -> Result<Self, String> {
+        let mut inputs = ObservationFeature::defaults();
+        let mut hidden_sizes: Vec<usize> = vec![3];
+        let mut activations: Vec<Activation> = Vec::new();
+        let mut options = Self {
+            settings: GeneticAlgorithmSettings::default(),
+            generations: GENERATIONS,
+            threads: None,
+            checkpoint: None,
+            checkpoint_every: 10,
+            resume: None,
+            metrics: None,
+            labels: None,
+            output: DEFAULT_OUTPUT_PATH.into(),
+        };
+
+        while let Some(arg) = args.next() {
+            if arg == "-h" || arg == "--help" {
+                println!("{USAGE}");
+                process::exit(0);
+            }
+
+            let value = args
+                .next()
+                .ok_or_else(|| format!("Missing value for '{arg}'"))?;
+            match arg.as_str() {
+                "--pop-size" => options.settings.pop_size = parse_value(&arg, &value)?,
+                "--inputs" => inputs = parse_list(&arg, &value)?,
+                "--hidden" => {
+                    hidden_sizes = if value == "none" {
+                        Vec::new()
+                    } else {
+                        parse_list(&arg, &value)?
+                    }
+                }
+                "--activations" => activations = parse_list(&arg, &value)?,
+                "--learning-rate" => {
+                    options.settings.weight_mutation.magnitude = parse_value(&arg, &value)?
+                }
+                "--mutation-rate" => {
+                    options.settings.weight_mutation.probability = parse_value(&arg, &value)?
+                }
+                "--bias-learning-rate" => {
+                    options.settings.bias_mutation.magnitude = parse_value(&arg, &value)?
+                }
+                "--bias-mutation-rate" => {
+                    options.settings.bias_mutation.probability = parse_value(&arg, &value)?
+                }
+                "--noise" => {
+                    let noise: MutationNoise = value.parse()?;
+                    options.settings.weight_mutation.noise = noise;
+                    options.settings.bias_mutation.noise = noise;
+                }
+                "--generations" => options.generations = parse_value(&arg, &value)?,
+                "--seed" => options.settings.seed = parse_value(&arg, &value)?,
+                "--shaping" => options.settings.shaping = parse_value(&arg, &value)?,
+                "--elitism" => options.settings.elitism = parse_value(&arg, &value)?,
+                "--selection" => options.settings.selection = value.parse()?,
+                "--threads" => options.threads = Some(parse_value(&arg, &value)?),
+                "--checkpoint" => options.checkpoint = Some(value),
+                "--checkpoint-every" => options.checkpoint_every = parse_value(&arg, &value)?,
+                "--resume" => options.resume = Some(value),
+                "--metrics" => options.metrics = Some(value),
+                "--labels" => options.labels = Some(value),
+                "--output" => options.output = value,
+                _ => return Err(format!("Unknown option '{arg}'")),
+            }
+        }
+
+        options.settings.topology = NetworkTopology::new(&inputs, &hidden_sizes, &activations)?;
+
+        if options.settings.pop_size < 2 {
+            return Err("'--pop-size' must be at least 2".into());
+        }
+
+        if !(0.0..=1.0).contains(&options.settings.weight_mutation.probability) {
+            return Err("'--mutation-rate' must be between 0 and 1".into());
+        }
+
+        if !(0.0..=1.0).contains(&options.settings.bias_mutation.probability) {
+            return Err("'--bias-mutation-rate' must be between 0 and 1".into());
+        }
+
+        if !(0.0..=1.0).contains(&options.settings.shaping) {
+            return Err("'--shaping' must be between 0 and 1".into());
+        }
+
+        Ok(options)
+    }
+}
+
+enum MetricsFormat {
+    Csv,
+    JsonLines,
+}
+
+impl MetricsFormat {
+    fn row_generation(&self, line: &str) -> Option<u32> {
+        match self {
+            MetricsFormat::Csv => line.split(',').next()?.parse().ok(),
+            MetricsFormat::JsonLines => serde_json::from_str::<serde_json::Value>(line)
+                .ok()?
+                .get("generation")?
+                .as_u64()
+                .map(|generation| generation as u32),
+        }
+    }
+}
+
+struct MetricsWriter {
+    format: MetricsFormat,
+    writer: BufWriter<File>,
+}
+
+impl MetricsWriter {
+    const CSV_HEADER: &'static str = "generation,best_score,mean_score,median_score,worst_score,\
+        diversity,best_head,best_body,best_legs,best_miss,head,body,legs,miss";
+
+    fn open(path: &str, resume_from: Option<u32>) -> Result<Self, String> {
+        let format = match Path::new(path).extension().and_then(|ext| ext.to_str()) {
+            Some("json") | Some("jsonl") => MetricsFormat::JsonLines,
+            _ => MetricsFormat::Csv,
+        };
+
+        if let Some(generation) = resume_from {
+            MetricsWriter::truncate_to(path, &format, generation)?;
+        }
+
+        let file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .append(resume_from.is_some())
+            .truncate(resume_from.is_none())
+            .open(path)
+            .map_err(|error| format!("Failed to open '{path}': {error}"))?;
+        let is_empty = file.metadata().map_or(true, |metadata| metadata.len() == 0);
+
+        let mut metrics_writer = Self {
+            format,
+            writer: BufWriter::new(file),
+        };
+        if is_empty {
+            if let MetricsFormat::Csv = metrics_writer.format {
+                writeln!(metrics_writer.writer, "{}", MetricsWriter::CSV_HEADER)
+                    .map_err(|error| format!("Failed to write '{path}': {error}"))?;
+            }
+        }
+
+        Ok(metrics_writer)
+    }
+
+    fn truncate_to(path: &str, format: &MetricsFormat, generation: u32) -> Result<(), String> {
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(error) if error.kind() == ErrorKind::NotFound => return Ok(()),
+            Err(error) => return Err(format!("Failed to read '{path}': {error}")),
+        };
+
+        let mut kept = String::with_capacity(contents.len());
+        for line in contents.lines().filter(|line| !line.is_empty()) {
+            match format.row_generation(line) {
+                Some(row_generation) if row_generation >= generation => break,
+                _ => {
+                    kept.push_str(line);
+                    kept.push('\n');
+                }
+            }
+        }
+
+        fs::write(path, kept).map_err(|error| format!("Failed to write '{path}': {error}"))
+    }
+
+    fn write(&mut self, stats: &GenerationStats) -> std::io::Result<()> {
+        match self.format {
+            MetricsFormat::Csv => writeln!(
+                self.writer,
+                "{},{},{},{},{},{},{},{},{},{},{},{},{},{}",
+                stats.generation,
+                stats.best_score,
+                stats.mean_score,
+                stats.median_score,
+                stats.worst_score,
+                stats.diversity,
+                stats.best_hits.head,
+                stats.best_hits.body,
+                stats.best_hits.legs,
+                stats.best_hits.miss,
+                stats.population_hits.head,
+                stats.population_hits.body,
+                stats.population_hits.legs,
+                stats.population_hits.miss
+            )?,
+            MetricsFormat::JsonLines => {
+                serde_json::to_writer(&mut self.writer, stats)?;
+                writeln!(self.writer)?;
+            }
+        }
+
+        self.writer.flush()
+    }
+}
+
+fn write_labels(path: &str, shots: &[LabeledShot]) -> std::io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    match Path::new(path).extension().and_then(|ext| ext.to_str()) {
+        Some("json") | Some("jsonl") => {
+            for shot in shots {
+                serde_json::to_writer(&mut writer, shot)?;
+                writeln!(writer)?;
+            }
+        }
+        _ => {
+            writeln!(writer, "self_height,enemy_height,power,angle")?;
+            for shot in shots {
+                writeln!(
+                    writer,
+                    "{},{},{},{}",
+                    shot.self_height, shot.enemy_height, shot.power, shot.angle
+                )?;
+            }
+        }
+    }
+
+    writer.flush()
+}
+
+fn parse_value<T: std::str::FromStr>(arg: &str, value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("Invalid value '{value}' for '{arg}'"))
+}
+
+fn parse_list<T: std::str::FromStr>(arg: &str, value: &str) -> Result<Vec<T>, String> {
+    value
+        .split(',')
+        .map(|item| parse_value(arg, item.trim()))
+        .collect()
+}
+
+fn main() {
+    let options = match TrainOptions::from_args(env::args().skip(1)) {
+        Ok(options) => options,
+        Err(error) => {
+            eprintln!("{error}\n\n{USAGE}");
+            process::exit(2);
+        }
+    };
+
+    if let Some(threads) = options.threads {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build_global()
+            .expect("Failed to build thread pool!");
+    }
+
+    if let Some(path) = &options.labels {
+        let shots = ballistics::labeled_samples(LABEL_GRID_STEPS);
+        if let Err(error) = write_labels(path, &shots) {
+            eprintln!("Failed to write labels to '{path}': {error}");
+            process::exit(1);
+        }
+        println!("Saved {} labelled shots to '{path}'", shots.len());
+    }
+
+    let mut genetic_algorithm = match &options.resume {
+        Some(path) => match GeneticAlgorithm::resume(path) {
+            Ok(genetic_algorithm) => {
+                println!(
+                    "Resuming from generation {} of '{}'",
+                    genetic_algorithm.current_generation(),
+                    path
+                );
+                genetic_algorithm
+            }
+            Err(error) => {
+                eprintln!("{error}");
+                process::exit(1);
+            }
+        },
+        None => GeneticAlgorithm::new(options.settings),
+    };
+
+    if let Some(path) = &options.checkpoint {
+        genetic_algorithm = genetic_algorithm.with_checkpoints(path, options.checkpoint_every);
+    }
+
+    let resume_from = options
+        .resume
+        .as_ref()
+        .map(|_| genetic_algorithm.current_generation());
+    let mut metrics_writer = options.metrics.as_ref().map(|path| {
+        MetricsWriter::open(path, resume_from).unwrap_or_else(|error| {
+            eprintln!("{error}");
+            process::exit(1);
+        })
+    });
+
+    println!(
+        "Training for {} generations with {:?}",
+        options.generations,
+        genetic_algorithm.settings()
+    );
+
+    let best_net = genetic_algorithm.get_best_with_progress(options.generations, |stats| {
+        println!(
+            "Generation {:>4}: best {:>6}, mean {:>9.2}, median {:>7.1}, worst {:>6}, diversity {:.4}",
+            stats.generation,
+            stats.best_score,
+            stats.mean_score,
+            stats.median_score,
+            stats.worst_score,
+            stats.diversity
+        );
+
+        if let Some(metrics_writer) = metrics_writer.as_mut() {
+            if let Err(error) = metrics_writer.write(stats) {
+                eprintln!("Failed to write metrics: {error}");
+            }
+        }
+    });
+    let best_net = best_net.unwrap_or_else(|error| {
+        eprintln!("{error}");
+        process::exit(1);
+    });
+
+    let model = AIModel::new(
+        best_net,
+        Some(TrainingMetadata {
+            settings: genetic_algorithm.settings().clone(),
+            generations: options.generations,
+        }),
+    );
+    if let Err(error) = model.save(&options.output) {
+        eprintln!("Failed to write net to '{}': {error}", options.output);
+        process::exit(1);
+    }
+
+    println!(
+        "Saved best net with score {} to '{}'",
+        model.metadata.score, options.output
+    );
+
+    let (oracle_score, oracle_hits) = ballistics::oracle_score();
+    println!(
+        "Analytical solver scores {} ({} head, {} body, {} legs, {} miss), the net reaches {:.1}% of it",
+        oracle_score,
+        oracle_hits.head,
+        oracle_hits.body,
+        oracle_hits.legs,
+        oracle_hits.miss,
+        model.metadata.score as f32 / oracle_score.max(1) as f32 * 100.0
+    );
+}
@@ -1,27 +1,48 @@
 use bevy::app::AppExit;
 use bevy::sprite::Anchor;
 use bevy::{prelude::*, render::camera::ScalingMode};
-use rand::Rng;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::env;
 
-use self::ai_controls::{AIControls, AIControlsPlugin};
+use self::ai_controls::{AIControls, AIControlsPlugin, AITraining};
 use self::animation::AnimationPlugin;
 use self::archer::{spawn_archer, Archer, ArcherEnemy, ArcherPlayer, ArcherPlugin, DamageReceiver};
-use self::arrow::{Arrow, ArrowPlugin};
-use self::collision::{CollisionPlugin, RectCollider};
+use self::arena::ArenaPlugin;
+use self::arrow::{Arrow, ArrowPlugin, ArrowStage};
+use self::collision::{
+    CollisionLayer, CollisionMask, CollisionPlugin, CollisionStarted, CollisionSystem, RectCollider,
+};
+use self::difficulty::{AdaptiveDifficulty, Difficulty, DifficultyPlugin};
+use self::geometry::Hitbox;
+use self::neural_network::{HitZone, NeuralNetwork};
 use self::player_controls::{PlayerControls, PlayerControlsPlugin};
 
-mod ai_controls;
+pub mod ai_controls;
+pub mod ai_model;
 mod animation;
 mod archer;
+pub mod arena;
 mod arrow;
+pub mod ballistics;
 mod collision;
+mod difficulty;
+pub mod genetic_algorithm;
+pub mod geometry;
+pub mod neural_network;
+pub mod observation;
 mod player_controls;
+pub mod strategy;
 
-const DIFFICULTY: f32 = 0.8;
+const SEED_ENV_VAR: &str = "ARCHE_SEED";
+
+pub const MAX_HEALTH: i32 = 10;
 
 const ROT_AXIS_Z: Vec3 = Vec3::new(0.0, 0.0, 1.0);
 const CAMERA_SCALING_MENU: f32 = 6.0;
 const CAMERA_SCALING_GAME: f32 = 17.0;
+const TRAINING_BAR_WIDTH: f32 = 4.0;
+const ADAPTIVE_REPORT_BAR_SPACING: f32 = 0.25;
+const ADAPTIVE_REPORT_MAX_BARS: usize = 40;
 
 pub struct GamePlugin;
 
@@ -33,6 +54,9 @@ impl Plugin for GamePlugin {
             .add_plugin(ArcherPlugin)
             .add_plugin(ArrowPlugin)
             .add_plugin(CollisionPlugin)
+            .add_plugin(DifficultyPlugin)
+            .add_plugin(ArenaPlugin)
+            .insert_resource(GameRandom::from_env())
             .add_startup_system_set_to_stage(
                 StartupStage::PreStartup,
                 SystemSet::new()
@@ -40,8 +64,16 @@ impl Plugin for GamePlugin {
                     .with_system(setup_resources),
             )
             .add_system(setup_game_stage_update_system)
-            .add_system(menu_buttons_update_system)
-            .add_system(game_arrow_update_system)
+            .add_system_to_stage(
+                ArrowStage,
+                menu_buttons_update_system.after(CollisionSystem::Detect),
+            )
+            .add_system(menu_training_update_system)
+            .add_system(menu_difficulty_update_system)
+            .add_system_to_stage(
+                ArrowStage,
+                game_arrow_update_system.after(CollisionSystem::Detect),
+            )
             .add_system(finished_game_update_system);
     }
 }
@@ -86,7 +118,7 @@ pub struct GameStageSpawned;
 #[derive(Component)]
 pub struct DespawnedOnNewTurn;
 
-#[derive(PartialEq, Eq)]
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
 pub enum GameTurn {
     Player,
     Enemy,
@@ -104,6 +136,21 @@ pub struct GameState {
     pub enemy_height: f32,
     pub player_health: i32,
     pub enemy_health: i32,
+    pub shot_history: Vec<ShotRecord>,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct ShotRecord {
+    pub shooter: GameTurn,
+    pub damage: i32,
+    pub offset: f32,
+    pub zone: HitZone,
+}
+
+impl ShotRecord {
+    pub fn hit(&self) -> bool {
+        self.damage > 0
+    }
 }
 
 impl GameState {
@@ -117,18 +164,87 @@ impl GameState {
             turn_count: -1,
             player_height: 0.5,
             enemy_height: 0.5,
-            player_health: 10,
-            enemy_health: 10,
+            player_health: MAX_HEALTH,
+            enemy_health: MAX_HEALTH,
+            shot_history: Vec::new(),
         }
     }
+
+    /// Puts health, shot history and turn order back to where a fresh match starts.
+    pub fn reset_match(&mut self) {
+        self.turn = GameTurn::Enemy;
+        self.turn_count = -1;
+        self.player_health = MAX_HEALTH;
+        self.enemy_health = MAX_HEALTH;
+        self.shot_history.clear();
+    }
+
+    pub fn next_turn(&mut self) {
+        self.turn = match self.turn {
+            GameTurn::Player => GameTurn::Enemy,
+            GameTurn::Enemy => GameTurn::Player,
+        };
+        self.turn_count += 1;
+    }
+
+    /// Records a simulated shot and deals its damage, returning whether it won the match.
+    pub fn apply_shot(&mut self, shooter: GameTurn, power: f32, angle: f32, zone: HitZone) -> bool {
+        let (self_height, enemy_height) = match shooter {
+            GameTurn::Player => (self.player_height, self.enemy_height),
+            GameTurn::Enemy => (self.enemy_height, self.player_height),
+        };
+        let damage = zone.damage();
+        self.shot_history.push(ShotRecord {
+            shooter,
+            damage,
+            offset: ballistics::shot_offset(power, angle, self_height, enemy_height),
+            zone,
+        });
+
+        let target_health = match shooter {
+            GameTurn::Player => &mut self.enemy_health,
+            GameTurn::Enemy => &mut self.player_health,
+        };
+        *target_health -= damage;
+        *target_health <= 0
+    }
+}
+
+#[derive(Resource, Deref, DerefMut)]
+pub struct GameRandom(StdRng);
+
+impl GameRandom {
+    pub fn new(seed: u64) -> Self {
+        Self(StdRng::seed_from_u64(seed))
+    }
+
+    pub fn from_env() -> Self {
+        match env::var(SEED_ENV_VAR)
+            .ok()
+            .and_then(|seed| seed.parse().ok())
+        {
+            Some(seed) => GameRandom::new(seed),
+            None => Self(StdRng::from_entropy()),
+        }
+    }
+}
+
+#[derive(Component, Clone, Copy)]
+enum TrainingProgressBar {
+    Generation,
+    BestScore,
 }
 
+#[derive(Component)]
+struct TrainingProgressFill;
+
 #[derive(Component)]
 enum MenuButton {
     Start,
     Credits,
     BackFromCredits,
     Quit,
+    Difficulty(Difficulty),
 }
 
 fn setup_camera(mut commands: Commands) {
@@ -215,6 +331,9 @@ fn setup_game_stage_update_system(
     mut game_state: ResMut<GameState>,
     mut player_controls: ResMut<PlayerControls>,
     mut ai_controls: ResMut<AIControls>,
+    mut random: ResMut<GameRandom>,
+    difficulty: Res<Difficulty>,
+    adaptive_difficulty: Res<AdaptiveDifficulty>,
     game_textures: Res<GameTextures>,
     mut stage_spawned: Query<(Entity, &mut Visibility, &GameStageSpawned)>,
     despawned_on_new_turn: Query<Entity, With<DespawnedOnNewTurn>>,
@@ -247,8 +366,7 @@ fn setup_game_stage_update_system(
             camera_projection.scaling_mode = ScalingMode::FixedVertical(CAMERA_SCALING_MENU);
             player_controls.set_enabled(true);
             player_controls.reset();
-            game_state.player_health = 10;
-            game_state.enemy_health = 10;
+            game_state.reset_match();
 
             commands
                 .spawn(SpriteBundle {
@@ -272,7 +390,7 @@ fn setup_game_stage_update_system(
                 .insert(GameStageSpawned)
                 .id();
 
-            spawn_archer(&mut commands, &game_textures, archer_player, true);
+            spawn_archer(&mut commands, &game_textures, archer_player, false, true);
 
             let start = commands
                 .spawn(SpriteBundle {
@@ -290,7 +408,7 @@ fn setup_game_stage_update_system(
 
             commands
                 .entity(start)
-                .insert(RectCollider::new(start.into(), Vec2::ZERO, 1.0, 1.0));
+                .insert(menu_button_collider(start, 1.0, 1.0));
 
             let credits = commands
                 .spawn(SpriteBundle {
@@ -306,12 +424,9 @@ fn setup_game_stage_update_system(
                 .insert(GameStageSpawned)
                 .id();
 
-            commands.entity(credits).insert(RectCollider::new(
-                credits.into(),
-                Vec2::ZERO,
-                1.0,
-                1.0,
-            ));
+            commands
+                .entity(credits)
+                .insert(menu_button_collider(credits, 1.0, 1.0));
 
             let quit = commands
                 .spawn(SpriteBundle {
@@ -329,7 +444,65 @@ fn setup_game_stage_update_system(
 
             commands
                 .entity(quit)
-                .insert(RectCollider::new(quit.into(), Vec2::ZERO, 1.0, 1.0));
+                .insert(menu_button_collider(quit, 1.0, 1.0));
+
+            for (index, difficulty) in Difficulty::ALL.into_iter().enumerate() {
+                let difficulty_button = commands
+                    .spawn(SpriteBundle {
+                        sprite: Sprite {
+                            color: difficulty.color(),
+                            custom_size: Vec2::new(0.5, 0.5).into(),
+                            ..default()
+                        },
+                        transform: Transform::from_translation(Vec3::new(
+                            5.3,
+                            1.5 - index as f32,
+                            0.1,
+                        )),
+                        ..default()
+                    })
+                    .insert(MenuButton::Difficulty(difficulty))
+                    .insert(GameStageSpawned)
+                    .id();
+
+                commands
+                    .entity(difficulty_button)
+                    .insert(menu_button_collider(difficulty_button, 0.5, 0.5));
+            }
+
+            for (bar, y) in [
+                (TrainingProgressBar::Generation, 2.6),
+                (TrainingProgressBar::BestScore, 2.35),
+            ] {
+                commands
+                    .spawn(SpriteBundle {
+                        sprite: Sprite {
+                            color: Color::rgba(0.0, 0.0, 0.0, 0.5),
+                            custom_size: Vec2::new(TRAINING_BAR_WIDTH, 0.15).into(),
+                            anchor: Anchor::CenterLeft,
+                            ..default()
+                        },
+                        transform: Transform::from_translation(Vec3::new(-5.5, y, 0.2)),
+                        visibility: Visibility::INVISIBLE,
+                        ..default()
+                    })
+                    .insert(bar)
+                    .insert(GameStageSpawned)
+                    .with_children(|parent| {
+                        parent
+                            .spawn(SpriteBundle {
+                                sprite: Sprite {
+                                    color: Color::WHITE,
+                                    custom_size: Vec2::new(0.0, 0.15).into(),
+                                    anchor: Anchor::CenterLeft,
+                                    ..default()
+                                },
+                                transform: Transform::from_translation(Vec3::new(0.0, 0.0, 0.1)),
+                                ..default()
+                            })
+                            .insert(TrainingProgressFill);
+                    });
+            }
         }
         GameStage::Credits => {
             clear_scene();
@@ -359,7 +532,7 @@ fn setup_game_stage_update_system(
                 .insert(GameStageSpawned)
                 .id();
 
-            spawn_archer(&mut commands, &game_textures, archer_player, true);
+            spawn_archer(&mut commands, &game_textures, archer_player, false, true);
 
             commands
                 .spawn(SpriteBundle {
@@ -389,7 +562,7 @@ fn setup_game_stage_update_system(
 
             commands
                 .entity(back)
-                .insert(RectCollider::new(back.into(), Vec2::ZERO, 1.0, 1.0));
+                .insert(menu_button_collider(back, 1.0, 1.0));
         }
         GameStage::StartGame => {
             clear_scene();
@@ -409,7 +582,7 @@ fn setup_game_stage_update_system(
 
             let archer_player = commands
                 .spawn(SpatialBundle {
-                    transform: Transform::from_translation(Vec3::new(-12.0, 0.0, 0.2)),
+                    transform: geometry::archer_transform(false),
                     ..default()
                 })
                 .insert(Archer::new(false))
@@ -435,8 +608,7 @@ fn setup_game_stage_update_system(
 
             let archer_enemy = commands
                 .spawn(SpatialBundle {
-                    transform: Transform::from_translation(Vec3::new(12.0, 0.0, 0.2))
-                        .with_scale(Vec3::new(-1.0, 1.0, 1.0)),
+                    transform: geometry::archer_transform(true),
                     ..default()
                 })
                 .insert(Archer::new(true))
@@ -460,16 +632,18 @@ fn setup_game_stage_update_system(
 
             commands.entity(archer_enemy).add_child(tower_enemy);
 
-            spawn_archer(&mut commands, &game_textures, archer_player, true);
-            spawn_archer(&mut commands, &game_textures, archer_enemy, false);
+            spawn_archer(&mut commands, &game_textures, archer_player, false, true);
+            spawn_archer(&mut commands, &game_textures, archer_enemy, true, false);
+
+            ai_controls.start_match(random.gen());
 
             game_state.stage = GameStage::ChangeTurn;
             game_state.needs_refresh = true;
         }
         GameStage::Playing => {
             player_controls.reset();
-            game_state.player_height = rand::thread_rng().gen_range(0.0..=1.0);
-            game_state.enemy_height = rand::thread_rng().gen_range(0.0..=1.0);
+            game_state.player_height = random.gen_range(0.0..=1.0);
+            game_state.enemy_height = random.gen_range(0.0..=1.0);
             game_state.waiting_for_hit = false;
             player_controls.set_enabled(match game_state.turn {
                 GameTurn::Player => true,
@@ -477,8 +651,8 @@ fn setup_game_stage_update_system(
             });
             ai_controls.set_enabled(true);
 
-            let player_height = (game_state.player_height * 12.0) - (17.0 * 0.5) + 1.0;
-            let enemy_height = (game_state.enemy_height * 12.0) - (17.0 * 0.5) + 1.0;
+            let player_height = geometry::tower_top(game_state.player_height);
+            let enemy_height = geometry::tower_top(game_state.enemy_height);
 
             for mut transform in archers_player.iter_mut() {
                 transform.translation.y = player_height;
@@ -493,11 +667,7 @@ fn setup_game_stage_update_system(
                 commands.entity(entity).despawn_recursive();
             }
 
-            game_state.turn = match &game_state.turn {
-                GameTurn::Player => GameTurn::Enemy,
-                GameTurn::Enemy => GameTurn::Player,
-            };
-            game_state.turn_count += 1;
+            game_state.next_turn();
             game_state.stage = GameStage::Playing;
             game_state.needs_refresh = true;
         }
@@ -533,62 +703,166 @@ fn setup_game_stage_update_system(
                     ..default()
                 })
                 .insert(GameStageSpawned);
+
+            let history = adaptive_difficulty.history();
+            let shown = &history[history.len().saturating_sub(ADAPTIVE_REPORT_MAX_BARS)..];
+            let start_x = -(shown.len() as f32 - 1.0) * 0.5 * ADAPTIVE_REPORT_BAR_SPACING;
+            for (index, steps) in shown.iter().enumerate() {
+                commands
+                    .spawn(SpriteBundle {
+                        sprite: Sprite {
+                            color: AdaptiveDifficulty::step_color(*difficulty, *steps),
+                            custom_size: Vec2::new(
+                                ADAPTIVE_REPORT_BAR_SPACING * 0.8,
+                                AdaptiveDifficulty::step_height(*steps),
+                            )
+                            .into(),
+                            anchor: Anchor::BottomCenter,
+                            ..default()
+                        },
+                        transform: Transform::from_translation(Vec3::new(
+                            start_x + index as f32 * ADAPTIVE_REPORT_BAR_SPACING,
+                            -2.8,
+                            0.2,
+                        )),
+                        ..default()
+                    })
+                    .insert(GameStageSpawned);
+            }
+        }
+    }
+}
+
+fn menu_training_update_system(
+    ai_controls: Res<AIControls>,
+    ai_training: Res<AITraining>,
+    mut buttons: Query<(&MenuButton, &mut Sprite)>,
+    mut bars: Query<(&TrainingProgressBar, &mut Visibility, &Children), Without<MenuButton>>,
+    mut fills: Query<&mut Sprite, (With<TrainingProgressFill>, Without<MenuButton>)>,
+) {
+    for (button, mut sprite) in buttons.iter_mut() {
+        if let MenuButton::Start = button {
+            sprite.color = if ai_controls.ready() {
+                Color::WHITE
+            } else {
+                Color::rgba(0.4, 0.4, 0.4, 0.6)
+            };
+        }
+    }
+
+    let progress = ai_training.progress();
+    for (bar, mut visibility, children) in bars.iter_mut() {
+        visibility.is_visible = ai_training.is_running();
+
+        let fraction = match bar {
+            TrainingProgressBar::Generation => {
+                progress.generation as f32 / progress.generations.max(1) as f32
+            }
+            TrainingProgressBar::BestScore => {
+                progress.best_score as f32 / NeuralNetwork::max_score() as f32
+            }
+        };
+        for &child in children.iter() {
+            if let Ok(mut fill) = fills.get_mut(child) {
+                fill.custom_size =
+                    Vec2::new(TRAINING_BAR_WIDTH * fraction.clamp(0.0, 1.0), 0.15).into();
+            }
+        }
+    }
+}
+
+fn menu_difficulty_update_system(
+    difficulty: Res<Difficulty>,
+    mut buttons: Query<(&MenuButton, &mut Sprite, &mut Transform)>,
+) {
+    for (button, mut sprite, mut transform) in buttons.iter_mut() {
+        if let MenuButton::Difficulty(level) = button {
+            let selected = *level == *difficulty;
+            sprite.color = level.color();
+            sprite.color.set_a(if selected { 1.0 } else { 0.4 });
+            transform.scale = Vec3::splat(if selected { 1.3 } else { 1.0 });
         }
     }
 }
 
+fn menu_button_collider(button: Entity, width: f32, height: f32) -> RectCollider {
+    RectCollider::new(button.into(), Vec2::ZERO, width, height).with_layer(
+        CollisionLayer::MenuButton,
+        CollisionMask::NONE.with(CollisionLayer::Arrow),
+    )
+}
+
 fn menu_buttons_update_system(
     mut game_state: ResMut<GameState>,
     mut exit: EventWriter<AppExit>,
-    buttons: Query<(&MenuButton, &RectCollider)>,
+    ai_controls: Res<AIControls>,
+    mut difficulty: ResMut<Difficulty>,
+    mut collisions: EventReader<CollisionStarted>,
+    buttons: Query<&MenuButton>,
     mut arrows: Query<(&mut Arrow, &mut RectCollider), Without<MenuButton>>,
 ) {
+    let collisions: Vec<CollisionStarted> = collisions.iter().copied().collect();
     if game_state.needs_refresh {
         return;
     }
 
-    for (button, collider) in buttons.iter() {
-        for (mut arrow, mut arrow_collider) in arrows.iter_mut() {
-            if collider.aabb_collides_with(&arrow_collider) {
-                match button {
-                    MenuButton::Start => {
-                        if game_state.stage == GameStage::Menu {
-                            arrow_collider.disable();
-                            arrow.set_moving(false);
-                            game_state.stage = GameStage::StartGame;
-                            game_state.needs_refresh = true;
-                            game_state.wait_for = 0.2;
-                        }
+    for collision in collisions {
+        let pair = collision.pair;
+        for (button_entity, arrow_entity) in [(pair.entity, pair.other), (pair.other, pair.entity)]
+        {
+            let (Ok(button), Ok((mut arrow, mut arrow_collider))) =
+                (buttons.get(button_entity), arrows.get_mut(arrow_entity))
+            else {
+                continue;
+            };
+
+            match button {
+                MenuButton::Start => {
+                    if game_state.stage == GameStage::Menu && ai_controls.ready() {
+                        arrow_collider.disable();
+                        arrow.set_moving(false);
+                        game_state.stage = GameStage::StartGame;
+                        game_state.needs_refresh = true;
+                        game_state.wait_for = 0.2;
                     }
-                    MenuButton::Credits => {
-                        if game_state.stage == GameStage::Menu {
-                            arrow_collider.disable();
-                            arrow.set_moving(false);
-                            game_state.stage = GameStage::Credits;
-                            game_state.needs_refresh = true;
-                            game_state.wait_for = 0.2;
-                        }
+                }
+                MenuButton::Credits => {
+                    if game_state.stage == GameStage::Menu {
+                        arrow_collider.disable();
+                        arrow.set_moving(false);
+                        game_state.stage = GameStage::Credits;
+                        game_state.needs_refresh = true;
+                        game_state.wait_for = 0.2;
                     }
-                    MenuButton::BackFromCredits => {
-                        if game_state.stage == GameStage::Credits {
-                            arrow_collider.disable();
-                            arrow.set_moving(false);
-                            game_state.stage = GameStage::Menu;
-                            game_state.needs_refresh = true;
-                            game_state.wait_for = 0.2;
-                        }
+                }
+                MenuButton::BackFromCredits => {
+                    if game_state.stage == GameStage::Credits {
+                        arrow_collider.disable();
+                        arrow.set_moving(false);
+                        game_state.stage = GameStage::Menu;
+                        game_state.needs_refresh = true;
+                        game_state.wait_for = 0.2;
                     }
-                    MenuButton::Quit => {
-                        if game_state.stage == GameStage::Menu {
-                            arrow_collider.disable();
-                            arrow.set_moving(false);
-                            exit.send(AppExit);
-                        }
+                }
+                MenuButton::Quit => {
+                    if game_state.stage == GameStage::Menu {
+                        arrow_collider.disable();
+                        arrow.set_moving(false);
+                        exit.send(AppExit);
+                    }
+                }
+                MenuButton::Difficulty(level) => {
+                    if game_state.stage == GameStage::Menu {
+                        arrow_collider.disable();
+                        arrow.set_moving(false);
+                        *difficulty = *level;
+                        game_state.needs_refresh = true;
+                        game_state.wait_for = 0.2;
                     }
                 }
-
-                return;
             }
+
+            return;
         }
     }
 }
@@ -613,11 +887,11 @@ pub fn finished_game_update_system(
 fn game_arrow_update_system(
     mut commands: Commands,
     mut game_state: ResMut<GameState>,
-    mut arrows: Query<(Entity, &mut Arrow, &mut RectCollider, &Transform)>,
-    damage_receivers: Query<(&RectCollider, &DamageReceiver), Without<Arrow>>,
-    archers_player: Query<&ArcherPlayer>,
-    archers_enemy: Query<&ArcherEnemy>,
+    mut collisions: EventReader<CollisionStarted>,
+    mut arrows: Query<(Entity, &mut Arrow, &mut RectCollider, &mut Transform)>,
+    damage_receivers: Query<(&DamageReceiver, &RectCollider), Without<Arrow>>,
 ) {
+    let collisions: Vec<CollisionStarted> = collisions.iter().copied().collect();
     if !(game_state.stage == GameStage::Playing && game_state.waiting_for_hit)
         || game_state.needs_refresh
     {
@@ -628,46 +902,63 @@ fn game_arrow_update_system(
     let mut hit_archer = false;
     let mut killed_archer = false;
     let mut winner = GameTurn::Player;
-    for (arrow_entity, mut arrow, mut arrow_collider, arrow_transform) in arrows.iter_mut() {
+    let mut damage = 0;
+    let mut offset = 0.0;
+    let mut zone = HitZone::Miss;
+    let target = match game_state.turn {
+        GameTurn::Player => {
+            Hitbox::HEAD.center(geometry::archer_position(true, game_state.enemy_height))
+        }
+        GameTurn::Enemy => {
+            Hitbox::HEAD.center(geometry::archer_position(false, game_state.player_height))
+        }
+    };
+    for (arrow_entity, mut arrow, mut arrow_collider, mut arrow_transform) in arrows.iter_mut() {
         let arrow_pos = arrow_transform.translation.truncate();
+        offset = arrow.pos_at_x(target.x).unwrap_or(arrow_pos).y - target.y;
+
+        let hit = collisions
+            .iter()
+            .filter_map(|collision| {
+                let (other, other_owner) = collision.pair.other(arrow_entity)?;
+                other_owner?;
+                let (damage_receiver, hitbox_collider) = damage_receivers.get(other).ok()?;
+                let (victim, shooter) = match hitbox_collider.layer() {
+                    CollisionLayer::PlayerHitbox => (GameTurn::Player, GameTurn::Enemy),
+                    CollisionLayer::EnemyHitbox => (GameTurn::Enemy, GameTurn::Player),
+                    _ => return None,
+                };
+                let mut hit = collision.hit;
+                if collision.pair.entity != arrow_entity {
+                    // The hitbox moved into a resting arrow, so the arrow stays where it is.
+                    hit.position = arrow_pos;
+                }
+                Some((victim, shooter, damage_receiver, hit))
+            })
+            .min_by(|(.., a), (.., b)| a.time.total_cmp(&b.time));
 
-        for (damage_collider, damage_receiver) in damage_receivers.iter() {
-            if arrow_collider.owner == damage_collider.owner {
-                continue;
-            }
-
-            if arrow_collider.aabb_collides_with(damage_collider) {
-                if let Some(collider_owner) = damage_collider.owner {
-                    if archers_player.get(collider_owner).is_ok() {
-                        commands.entity(arrow_entity).insert(DespawnedOnNewTurn);
-                        arrow_collider.disable();
-                        arrow.set_moving(false);
-                        hit_archer = true;
-
-                        game_state.player_health -= damage_receiver.hitpoints;
-                        if game_state.player_health <= 0 {
-                            game_state.player_health = 0;
-
-                            killed_archer = true;
-                            winner = GameTurn::Enemy;
-                        }
-                    }
-
-                    if archers_enemy.get(collider_owner).is_ok() {
-                        commands.entity(arrow_entity).insert(DespawnedOnNewTurn);
-                        arrow_collider.disable();
-                        arrow.set_moving(false);
-                        hit_archer = true;
+        if let Some((victim, shooter, damage_receiver, hit)) = hit {
+            commands.entity(arrow_entity).insert(DespawnedOnNewTurn);
+            arrow_collider.set_center(hit.position);
+            arrow_collider.disable();
+            arrow.set_moving(false);
+            arrow_transform.translation = hit.position.extend(arrow_transform.translation.z);
+            hit_archer = true;
 
-                        game_state.enemy_health -= damage_receiver.hitpoints;
-                        if game_state.enemy_health <= 0 {
-                            game_state.enemy_health = 0;
+            damage += damage_receiver.hitpoints;
+            if damage_receiver.zone.score() > zone.score() {
+                zone = damage_receiver.zone;
+            }
+            let health = match victim {
+                GameTurn::Player => &mut game_state.player_health,
+                GameTurn::Enemy => &mut game_state.enemy_health,
+            };
+            *health -= damage_receiver.hitpoints;
+            if *health <= 0 {
+                *health = 0;
 
-                            killed_archer = true;
-                            winner = GameTurn::Player;
-                        }
-                    }
-                }
+                killed_archer = true;
+                winner = shooter;
             }
         }
 
@@ -680,6 +971,16 @@ fn game_arrow_update_system(
         }
     }
 
+    if arrow_out_of_bounds || hit_archer {
+        let shooter = game_state.turn;
+        game_state.shot_history.push(ShotRecord {
+            shooter,
+            damage,
+            offset,
+            zone,
+        });
+    }
+
     if killed_archer {
         game_state.stage = GameStage::Finished(winner);
         game_state.needs_refresh = true;
@@ -693,3 +994,253 @@ fn game_arrow_update_system(
         game_state.wait_for = 0.5;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use bevy::asset::AssetPlugin;
+    use bevy::ecs::system::CommandQueue;
+    use bevy::time::TimeUpdateStrategy;
+    use bevy::transform::TransformPlugin;
+
+    use super::ai_controls::AIControls;
+    use super::ballistics;
+    use super::observation::Observation;
+    use super::*;
+
+    const HEIGHTS: [f32; 3] = [0.0, 0.5, 1.0];
+    const MAX_SECONDS: f32 = 30.0;
+
+    struct ShotOutcome {
+        zone: HitZone,
+        landing: Option<Vec2>,
+    }
+
+    fn spawn_test_archer(world: &mut World, flipped: bool, height: f32) -> Entity {
+        let mut transform = geometry::archer_transform(flipped);
+        transform.translation.y = geometry::tower_top(height);
+        let archer = world
+            .spawn(SpatialBundle::from_transform(transform))
+            .insert(Archer::new(flipped))
+            .id();
+
+        let mut queue = CommandQueue::default();
+        let mut commands = Commands::new(&mut queue, world);
+        spawn_archer(
+            &mut commands,
+            &GameTextures::default(),
+            archer,
+            flipped,
+            false,
+        );
+        queue.apply(world);
+
+        archer
+    }
+
+    fn launch_arrow(app: &mut App, archer: Entity, power: f32, angle: f32) {
+        // The bow is held for a frame so the shooting point has turned with the arm on release.
+        app.world
+            .get_mut::<Archer>(archer)
+            .unwrap()
+            .draw(angle, power);
+        app.update();
+        app.world.get_mut::<Archer>(archer).unwrap().release();
+    }
+
+    fn shot_app(
+        shooter: GameTurn,
+        power: f32,
+        angle: f32,
+        self_height: f32,
+        enemy_height: f32,
+    ) -> App {
+        let (player_height, enemy_height) = match shooter {
+            GameTurn::Player => (self_height, enemy_height),
+            GameTurn::Enemy => (enemy_height, self_height),
+        };
+        let mut game_state = GameState::new();
+        game_state.stage = GameStage::Playing;
+        game_state.needs_refresh = false;
+        game_state.waiting_for_hit = true;
+        game_state.turn = shooter;
+        game_state.player_height = player_height;
+        game_state.enemy_height = enemy_height;
+
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_plugin(TransformPlugin)
+            .add_plugin(AssetPlugin::default())
+            .add_asset::<Mesh>()
+            .add_asset::<ColorMaterial>()
+            .init_resource::<Input<KeyCode>>()
+            .add_plugin(ArcherPlugin)
+            .add_plugin(ArrowPlugin)
+            .add_plugin(CollisionPlugin)
+            .insert_resource(game_state)
+            .insert_resource(PlayerControls::default())
+            .insert_resource(AIControls::new())
+            .insert_resource(GameRandom::new(0))
+            .init_resource::<GameTextures>()
+            .init_resource::<Difficulty>()
+            .init_resource::<AdaptiveDifficulty>()
+            .add_system_to_stage(
+                ArrowStage,
+                game_arrow_update_system.after(CollisionSystem::Detect),
+            );
+
+        let player = spawn_test_archer(&mut app.world, false, player_height);
+        let enemy = spawn_test_archer(&mut app.world, true, enemy_height);
+        match shooter {
+            GameTurn::Player => launch_arrow(&mut app, player, power, angle),
+            GameTurn::Enemy => launch_arrow(&mut app, enemy, power, angle),
+        }
+
+        app
+    }
+
+    fn play_shot(
+        shooter: GameTurn,
+        power: f32,
+        angle: f32,
+        self_height: f32,
+        enemy_height: f32,
+        fps: f32,
+    ) -> ShotOutcome {
+        let mut app = shot_app(shooter, power, angle, self_height, enemy_height);
+        let start = Instant::now();
+        let frame_time = Duration::from_secs_f32(1.0 / fps);
+        for frame in 0..(MAX_SECONDS * fps) as u32 {
+            app.insert_resource(TimeUpdateStrategy::ManualInstant(
+                start + frame_time * frame,
+            ));
+            app.update();
+
+            if let Some(record) = app.world.resource::<GameState>().shot_history.last() {
+                let zone = record.zone;
+                let landing = app
+                    .world
+                    .query_filtered::<&Transform, With<Arrow>>()
+                    .iter(&app.world)
+                    .next()
+                    .map(|transform| transform.translation.truncate());
+                return ShotOutcome { zone, landing };
+            }
+        }
+
+        panic!("Arrow neither hit an archer nor left the arena");
+    }
+
+    #[test]
+    fn solver_shots_hit_the_head_in_game() {
+        for self_height in HEIGHTS {
+            for enemy_height in HEIGHTS {
+                let solution = ballistics::aim(self_height, enemy_height).unwrap();
+                let (power, angle) = (solution.power, solution.angle);
+                let simulated =
+                    NeuralNetwork::simulate_shot(power, angle, self_height, enemy_height);
+                assert_eq!(simulated, HitZone::Head);
+
+                for shooter in [GameTurn::Player, GameTurn::Enemy] {
+                    let outcome = play_shot(shooter, power, angle, self_height, enemy_height, 60.0);
+                    assert_eq!(
+                        outcome.zone, simulated,
+                        "{shooter:?} shot from {self_height} at {enemy_height}"
+                    );
+                    assert!(outcome.landing.is_some());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn short_shots_miss_in_game() {
+        for self_height in HEIGHTS {
+            for enemy_height in HEIGHTS {
+                let solution = ballistics::aim(self_height, enemy_height).unwrap();
+                let (power, angle) = (solution.power * 0.5, solution.angle);
+                let simulated =
+                    NeuralNetwork::simulate_shot(power, angle, self_height, enemy_height);
+                assert_eq!(simulated, HitZone::Miss);
+
+                for shooter in [GameTurn::Player, GameTurn::Enemy] {
+                    let outcome = play_shot(shooter, power, angle, self_height, enemy_height, 60.0);
+                    assert_eq!(
+                        outcome.zone, simulated,
+                        "{shooter:?} shot from {self_height} at {enemy_height}"
+                    );
+                    assert!(outcome.landing.is_none());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn shots_land_the_same_at_any_frame_rate() {
+        let fast = ballistics::BallisticSolver::for_archer(0.5)
+            .solve_for_power(ballistics::target_point(0.5, HitZone::Head).unwrap(), 1.0)
+            .first()
+            .copied()
+            .unwrap();
+        let shots = [
+            (0.0, 1.0, ballistics::aim(0.0, 1.0).unwrap()),
+            (1.0, 0.0, ballistics::aim(1.0, 0.0).unwrap()),
+            (0.5, 0.5, fast),
+        ];
+
+        for (self_height, enemy_height, solution) in shots {
+            let (power, angle) = (solution.power, solution.angle);
+            for shooter in [GameTurn::Player, GameTurn::Enemy] {
+                let reference = play_shot(shooter, power, angle, self_height, enemy_height, 240.0);
+                for fps in [5.0, 30.0] {
+                    let outcome = play_shot(shooter, power, angle, self_height, enemy_height, fps);
+                    assert_eq!(
+                        outcome.zone, reference.zone,
+                        "{shooter:?} shot at {fps} fps"
+                    );
+                    let (landing, reference_landing) =
+                        (outcome.landing.unwrap(), reference.landing.unwrap());
+                    assert!(landing.distance(reference_landing) < 1e-4);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn body_and_leg_shots_match_the_simulation_in_game() {
+        for zone in [HitZone::Body, HitZone::Legs] {
+            let target = ballistics::target_point(0.5, zone).unwrap();
+            let solution = ballistics::BallisticSolver::for_archer(0.5)
+                .best(target)
+                .unwrap();
+            let (power, angle) = (solution.power, solution.angle);
+            let simulated = NeuralNetwork::simulate_shot(power, angle, 0.5, 0.5);
+            assert_eq!(simulated, zone);
+
+            for shooter in [GameTurn::Player, GameTurn::Enemy] {
+                let outcome = play_shot(shooter, power, angle, 0.5, 0.5, 60.0);
+                assert_eq!(outcome.zone, simulated, "{shooter:?} shot at {zone:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn second_match_starts_on_turn_zero() {
+        let mut game_state = GameState::new();
+        game_state.next_turn();
+        let first = Observation::new(&game_state, game_state.turn);
+
+        for _ in 0..5 {
+            game_state.next_turn();
+        }
+        game_state.enemy_health = 0;
+        game_state.reset_match();
+        game_state.next_turn();
+        let second = Observation::new(&game_state, game_state.turn);
+
+        assert_eq!(second.turn, 0);
+        assert_eq!(game_state.turn, GameTurn::Player);
+        assert_eq!(second.enemy_health, first.enemy_health);
+    }
+}
@@ -1,347 +1,376 @@
-use bevy::prelude::*;
-use rand::Rng;
-use serde::{Deserialize, Serialize};
-use std::{env, fs};
-
-use super::{arrow::Arrow, collision::RectCollider, GameState};
-
-const POP_SIZE: usize = 1024;
-const LEARNING_RATE: f32 = 0.5;
-const GENERATIONS: u32 = 100;
-
-const NET_FILE_PATH: &str = "/assets/ai/net.txt";
+use std::{
+    env,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+
+use bevy::{
+    asset::{HandleId, LoadState},
+    prelude::*,
+    tasks::{AsyncComputeTaskPool, Task},
+};
+use futures_lite::future;
+
+use super::{
+    ai_model::{
+        arg_value, AIModel, AIModelLoader, ModelFileWatcher, ModelLocation, TrainingMetadata,
+    },
+    genetic_algorithm::{GenerationStats, GeneticAlgorithm, GeneticAlgorithmSettings},
+    neural_network::NeuralNetwork,
+    observation::Observation,
+    strategy::{Strategy, StrategyKind},
+    GameState, GameTurn,
+};
+
+pub const GENERATIONS: u32 = 100;
+
+pub const OPPONENT_ARG: &str = "--ai";
+pub const OPPONENT_ENV_VAR: &str = "ARCHE_AI";
 
 pub struct AIControlsPlugin;
 
 impl Plugin for AIControlsPlugin {
     fn build(&self, app: &mut App) {
-        app.insert_resource(AIControls::new());
+        app.add_asset::<AIModel>()
+            .init_asset_loader::<AIModelLoader>()
+            .insert_resource(AIControls::new())
+            .init_resource::<AITraining>()
+            .add_startup_system(load_ai_model_startup_system)
+            .add_system(ai_training_update_system)
+            .add_system(ai_model_file_update_system)
+            .add_system(ai_model_update_system);
     }
 }
 
 #[derive(Resource)]
 pub struct AIControls {
     is_enabled: bool,
-    net: NeuralNetwork,
+    strategies: Vec<StrategyKind>,
+    matches_started: usize,
+    strategy: Box<dyn Strategy>,
+    base_model: Handle<AIModel>,
+    model: Handle<AIModel>,
+    loaded_model: Option<HandleId>,
+    model_file: Option<ModelFileWatcher>,
+    net: Option<NeuralNetwork>,
     pull_power: f32,
     pull_angle: f32,
 }
 
-impl AIControls {
-    fn new() -> Self {
-        let net: NeuralNetwork;
+#[derive(Clone, Copy, Default)]
+pub struct TrainingProgress {
+    pub generation: u32,
+    pub generations: u32,
+    pub best_score: i32,
+}
 
-        let path = env::current_dir().unwrap();
-        let mut full_path: String = path.to_str().unwrap().into();
-        full_path.push_str(NET_FILE_PATH);
+type TrainedModel = (AIModel, Option<PathBuf>);
 
-        if let Ok(serialized_net) = fs::read_to_string(&full_path) {
-            net = serde_json::from_str(&serialized_net).expect("Failed to deserialize net!");
-        } else {
-            let mut genetic_algorithm = GeneticAlgorithm::new(POP_SIZE, LEARNING_RATE);
-            let best_net = genetic_algorithm.get_best(GENERATIONS);
-            let serialized_net =
-                serde_json::to_string(&best_net).expect("Failed to serialize net!");
-            fs::write(&full_path, &serialized_net).unwrap();
-            net = best_net;
-        }
+#[derive(Resource, Default)]
+pub struct AITraining {
+    task: Option<Task<Option<TrainedModel>>>,
+    progress: Arc<Mutex<TrainingProgress>>,
+}
+
+impl AITraining {
+    pub fn is_running(&self) -> bool {
+        self.task.is_some()
+    }
+
+    pub fn progress(&self) -> TrainingProgress {
+        *self.progress.lock().unwrap()
+    }
 
+    fn start(&mut self, location: ModelLocation) {
+        let progress = self.progress.clone();
+        *progress.lock().unwrap() = TrainingProgress {
+            generations: GENERATIONS,
+            ..default()
+        };
+
+        let task = AsyncComputeTaskPool::get().spawn(async move {
+            let model = AIControls::train_model(|stats| {
+                info!(
+                    "Training AI: generation {}/{}, best score {}",
+                    stats.generation, GENERATIONS, stats.best_score
+                );
+                let mut progress = progress.lock().unwrap();
+                progress.generation = stats.generation;
+                progress.best_score = progress.best_score.max(stats.best_score);
+            });
+            let model = match model {
+                Ok(model) => model,
+                Err(error) => {
+                    warn!("Failed to train AI model: {error}");
+                    return None;
+                }
+            };
+
+            let path = match location.save(&model) {
+                Ok(path) => {
+                    info!("Saved trained AI model to '{}'", path.display());
+                    Some(path)
+                }
+                Err(error) => {
+                    warn!("Failed to save trained AI model: {error}");
+                    None
+                }
+            };
+
+            Some((model, path))
+        });
+        self.task = Some(task);
+    }
+}
+
+impl AIControls {
+    pub(crate) fn new() -> Self {
+        let strategies = AIControls::resolve_strategies();
         Self {
             is_enabled: false,
-            net,
+            strategy: strategies[0].build(None, 0),
+            strategies,
+            matches_started: 0,
+            base_model: Handle::default(),
+            model: Handle::default(),
+            loaded_model: None,
+            model_file: None,
+            net: None,
             pull_power: 0.0,
             pull_angle: 0.0,
         }
     }
 
-    pub fn enabled(&self) -> bool {
-        self.is_enabled
+    fn resolve_strategies() -> Vec<StrategyKind> {
+        let value =
+            arg_value(env::args(), OPPONENT_ARG).or_else(|| env::var(OPPONENT_ENV_VAR).ok());
+        let strategies = value.map(|value| {
+            value
+                .split(',')
+                .map(|item| item.trim().parse::<StrategyKind>())
+                .collect::<Result<Vec<_>, _>>()
+        });
+
+        match strategies {
+            Some(Ok(strategies)) if !strategies.is_empty() => strategies,
+            Some(Err(error)) => {
+                warn!("{error}, using the neural network");
+                vec![StrategyKind::default()]
+            }
+            _ => vec![StrategyKind::default()],
+        }
     }
 
-    pub fn set_enabled(&mut self, value: bool) {
-        self.is_enabled = value;
+    fn train_model<F: FnMut(&GenerationStats)>(on_generation: F) -> Result<AIModel, String> {
+        let settings = GeneticAlgorithmSettings::default();
+        let mut genetic_algorithm = GeneticAlgorithm::new(settings.clone());
+        let best_net = genetic_algorithm.get_best_with_progress(GENERATIONS, on_generation)?;
+        Ok(AIModel::new(
+            best_net,
+            Some(TrainingMetadata {
+                settings,
+                generations: GENERATIONS,
+            }),
+        ))
     }
 
-    pub fn think(&mut self, game_state: &GameState) {
-        let input = vec![game_state.enemy_height, game_state.player_height];
-        let output = self.net.calculate_output(input);
-        self.pull_power = output[0];
-        self.pull_angle = output[1];
+    pub fn enabled(&self) -> bool {
+        self.is_enabled
     }
 
-    pub fn get_pull_power(&self) -> f32 {
-        self.pull_power
+    pub fn set_enabled(&mut self, value: bool) {
+        self.is_enabled = value;
     }
 
-    pub fn get_pull_angle(&self) -> f32 {
-        self.pull_angle
+    pub fn start_match(&mut self, seed: u64) {
+        let kind = self.strategies[self.matches_started % self.strategies.len()];
+        self.matches_started += 1;
+        self.strategy = kind.build(self.net.as_ref(), seed);
+        info!("AI strategy for this match: {kind:?}");
     }
-}
 
-#[derive(Serialize, Deserialize, Clone)]
-struct NeuralNetwork {
-    weights: Vec<Vec<Vec<f32>>>,
-    biases: Vec<Vec<f32>>,
-}
-
-impl NeuralNetwork {
-    fn new() -> Self {
-        let mut random = rand::thread_rng();
-        let weights = vec![
-            vec![
-                vec![random.gen_range(-1.0..=1.0), random.gen_range(-1.0..=1.0)],
-                vec![random.gen_range(-1.0..=1.0), random.gen_range(-1.0..=1.0)],
-                vec![random.gen_range(-1.0..=1.0), random.gen_range(-1.0..=1.0)],
-            ],
-            vec![
-                vec![random.gen_range(-1.0..=1.0), random.gen_range(-1.0..=1.0)],
-                vec![random.gen_range(-1.0..=1.0), random.gen_range(-1.0..=1.0)],
-            ],
-        ];
-
-        let biases = vec![
-            vec![
-                random.gen_range(-1.0..=1.0),
-                random.gen_range(-1.0..=1.0),
-                random.gen_range(-1.0..=1.0),
-            ],
-            vec![random.gen_range(-1.0..=1.0), random.gen_range(-1.0..=1.0)],
-        ];
-
-        Self { weights, biases }
+    pub fn ready(&self) -> bool {
+        self.strategy.ready()
     }
 
-    fn get_weight(&self, layer: usize, neuron: usize, input: usize) -> f32 {
-        self.weights[layer][neuron][input]
+    pub fn base_model(&self) -> Handle<AIModel> {
+        self.base_model.clone()
     }
 
-    fn get_bias(&self, layer: usize, neuron: usize) -> f32 {
-        self.biases[layer][neuron]
+    fn set_base_model(&mut self, handle: Handle<AIModel>) {
+        if self.model == self.base_model {
+            self.model = handle.clone();
+        }
+        self.base_model = handle;
     }
 
-    fn layers_count(&self) -> usize {
-        self.biases.len()
+    pub fn use_model(&mut self, handle: Handle<AIModel>) {
+        self.model = handle;
     }
 
-    fn neurons_count(&self, layer: usize) -> usize {
-        self.biases[layer].len()
+    pub fn think(&mut self, game_state: &GameState) {
+        let observation = Observation::new(game_state, GameTurn::Enemy);
+        if let Some(shot) = self.strategy.shoot(&observation) {
+            self.pull_power = shot.power;
+            self.pull_angle = shot.angle;
+        }
     }
 
-    fn connections_count(&self, layer: usize, neuron: usize) -> usize {
-        self.weights[layer][neuron].len()
+    pub fn get_pull_power(&self) -> f32 {
+        self.pull_power
     }
 
-    fn activation(net: f32) -> f32 {
-        1.0 / (1.0 + f32::exp(-net))
+    pub fn get_pull_angle(&self) -> f32 {
+        self.pull_angle
     }
+}
 
-    fn calculate_output(&self, input: Vec<f32>) -> Vec<f32> {
-        let mut neurons: Vec<Vec<f32>> = vec![vec![0.0, 0.0, 0.0], vec![0.0, 0.0]];
-
-        for n_layer in 0..self.layers_count() {
-            for n_neuron in 0..self.neurons_count(n_layer) {
-                neurons[n_layer][n_neuron] = self.get_bias(n_layer, n_neuron);
-                for n_connection in 0..self.connections_count(n_layer, n_neuron) {
-                    neurons[n_layer][n_neuron] +=
-                        input[n_connection] * self.get_weight(n_layer, n_neuron, n_connection);
+fn load_ai_model_startup_system(
+    asset_server: Res<AssetServer>,
+    mut models: ResMut<Assets<AIModel>>,
+    mut ai_controls: ResMut<AIControls>,
+    mut ai_training: ResMut<AITraining>,
+) {
+    let location = ModelLocation::resolve();
+    match location.existing() {
+        Some(path) => {
+            let model = match ModelLocation::asset_path(path) {
+                Some(asset_path) => asset_server.load(asset_path),
+                None => {
+                    ai_controls.model_file = Some(ModelFileWatcher::new(path.to_path_buf()));
+                    match AIModel::load(path) {
+                        Ok(model) => models.add(model),
+                        Err(error) => {
+                            warn!(
+                                "Failed to load AI model from '{}': {error}, using built-in model",
+                                path.display()
+                            );
+                            models.add(AIModel::builtin())
+                        }
+                    }
                 }
-
-                neurons[n_layer][n_neuron] = NeuralNetwork::activation(neurons[n_layer][n_neuron]);
-            }
+            };
+            ai_controls.set_base_model(model);
         }
-
-        neurons[1].clone()
-    }
-
-    fn mutate(&mut self, rate: f32) {
-        for layer in self.weights.iter_mut() {
-            for neuron in layer.iter_mut() {
-                for weight in neuron.iter_mut() {
-                    *weight += rand::thread_rng().gen_range(-1.0..=1.0) * rate;
-                }
-            }
+        None => {
+            info!("No AI model found, training a new one in the background");
+            ai_training.start(location);
         }
     }
+}
 
-    fn crossover(&mut self, other: &NeuralNetwork, alpha: f32) {
-        for n_layer in 0..self.layers_count() {
-            for n_neuron in 0..self.neurons_count(n_layer) {
-                for n_connection in 0..self.connections_count(n_layer, n_neuron) {
-                    let self_weight = self.get_weight(n_layer, n_neuron, n_connection);
-                    let other_weight = other.get_weight(n_layer, n_neuron, n_connection);
-                    let average = ((1.0 - alpha) * self_weight) + (alpha * other_weight);
-                    self.weights[n_layer][n_neuron][n_connection] = average;
-                }
+fn ai_training_update_system(
+    mut models: ResMut<Assets<AIModel>>,
+    mut ai_controls: ResMut<AIControls>,
+    mut ai_training: ResMut<AITraining>,
+) {
+    if let Some(task) = ai_training.task.as_mut() {
+        if let Some(trained) = future::block_on(future::poll_once(task)) {
+            ai_training.task = None;
+            if let Some((model, path)) = trained {
+                ai_controls.model_file = path.map(ModelFileWatcher::new);
+                ai_controls.set_base_model(models.add(model));
             }
         }
     }
+}
 
-    fn score(&self) -> i32 {
-        let mut score = 0;
-        for i in 0..=10 {
-            let h_self = i as f32 * 0.1;
-            for j in 0..=10 {
-                let h_other = j as f32 * 0.1;
-                let input = vec![h_self, h_other];
-                let output = self.calculate_output(input);
-                let shot_score = NeuralNetwork::eval_shot(output[0], output[1], h_self, h_other);
-                score += shot_score;
-            }
-        }
-
-        score
+fn ai_model_file_update_system(
+    time: Res<Time>,
+    mut models: ResMut<Assets<AIModel>>,
+    mut ai_controls: ResMut<AIControls>,
+) {
+    let Some(model_file) = ai_controls.model_file.as_mut() else {
+        return;
+    };
+
+    if !model_file.poll(time.delta()) {
+        return;
     }
 
-    pub fn eval_shot(power: f32, angle: f32, self_height: f32, enemy_height: f32) -> i32 {
-        let self_height_real = (self_height * 12.0) - (17.0 * 0.5) + 1.0;
-        let enemy_height_real = (enemy_height * 12.0) - (17.0 * 0.5) + 1.0;
-
-        let shooting_vec = Vec2::new(f32::cos(angle), f32::sin(angle)) * 2.55;
-        let mut shoot_pos = Vec2::new(-12.0, self_height_real);
-        shoot_pos += Vec2::new(0.3, 2.1);
-        shoot_pos += shooting_vec;
-
-        let mut arrow_col = RectCollider::new(None, Vec2::ZERO, 0.3, 0.3);
-        arrow_col.set_center(shoot_pos);
-
-        let enemy_pos = Vec2::new(12.0, enemy_height_real);
-        let mut enemy_legs_col = RectCollider::new(None, Vec2::ZERO, 0.8, 1.2);
-        let enemy_legs_pos = enemy_pos + Vec2::new(0.0, 0.7);
-        enemy_legs_col.set_center(enemy_legs_pos);
-
-        let mut enemy_body_col = RectCollider::new(None, Vec2::ZERO, 0.8, 1.0);
-        let enemy_body_pos = enemy_pos + Vec2::new(0.0, 1.9);
-        enemy_body_col.set_center(enemy_body_pos);
-
-        let mut enemy_head_col = RectCollider::new(None, Vec2::ZERO, 0.7, 0.7);
-        let enemy_head_pos = enemy_pos + Vec2::new(0.0, 2.8);
-        enemy_head_col.set_center(enemy_head_pos);
-
-        let mut t = 0.0;
-        loop {
-            let arrow_pos = Arrow::get_trajectory(power * 10.0, angle, t);
-            let arrow_col_pos = shoot_pos + arrow_pos;
-            arrow_col.set_center(arrow_col_pos);
-            if arrow_col.aabb_collides_with(&enemy_head_col) {
-                return 100;
-            }
-
-            if arrow_col.aabb_collides_with(&enemy_body_col) {
-                return 50;
+    match AIModel::load(model_file.path()) {
+        Ok(model) => {
+            info!("Reloading AI model from '{}'", model_file.path().display());
+            if let Some(base_model) = models.get_mut(&ai_controls.base_model) {
+                *base_model = model;
             }
-
-            if arrow_col.aabb_collides_with(&enemy_legs_col) {
-                return 30;
-            }
-
-            if arrow_col_pos.x > 12.0 || arrow_col_pos.y > 12.0 || arrow_col_pos.y < -12.0 {
-                return 0;
-            }
-
-            t += 0.01;
         }
+        Err(error) => warn!(
+            "Failed to reload AI model from '{}': {error}, keeping the current model",
+            model_file.path().display()
+        ),
     }
 }
 
-#[derive(Clone)]
-struct Agent {
-    net: NeuralNetwork,
-    score: i32,
-    fitness: f32,
-}
-
-struct GeneticAlgorithm {
-    pop_size: usize,
-    learning_rate: f32,
-    current_gen: u32,
-    agents: Vec<Agent>,
-}
-
-impl GeneticAlgorithm {
-    fn new(pop_size: usize, learning_rate: f32) -> Self {
-        Self {
-            pop_size,
-            learning_rate,
-            current_gen: 0,
-            agents: Vec::new(),
+fn ai_model_update_system(
+    asset_server: Res<AssetServer>,
+    mut model_events: EventReader<AssetEvent<AIModel>>,
+    mut models: ResMut<Assets<AIModel>>,
+    mut ai_controls: ResMut<AIControls>,
+) {
+    for event in model_events.iter() {
+        if let AssetEvent::Modified { handle } = event {
+            if *handle == ai_controls.model {
+                ai_controls.loaded_model = None;
+            }
         }
     }
 
-    pub fn get_best(&mut self, generations: u32) -> NeuralNetwork {
-        self.current_gen = 0;
-        self.init_random();
-
-        while self.current_gen < generations {
-            self.calculate_fitness();
-            self.new_generation();
-
-            self.current_gen += 1;
+    if ai_controls.loaded_model != Some(ai_controls.model.id()) {
+        if let Some(model) = models.get(&ai_controls.model) {
+            info!(
+                "AI model ready: layers {:?}, score {}",
+                model.metadata.layer_sizes, model.metadata.score
+            );
+            ai_controls.strategy.set_network(&model.net);
+            ai_controls.net = Some(model.net.clone());
+            ai_controls.loaded_model = Some(ai_controls.model.id());
         }
+    }
 
-        self.calculate_fitness();
-        let mut best: NeuralNetwork = self.agents[0].net.clone();
-        let mut best_fitness = f32::MIN;
-        for agent in self.agents.iter() {
-            if agent.fitness > best_fitness {
-                best = agent.net.clone();
-                best_fitness = agent.fitness;
+    if asset_server.get_load_state(&ai_controls.model) == LoadState::Failed {
+        if ai_controls.model == ai_controls.base_model {
+            if ai_controls.net.is_none() {
+                warn!("Failed to load AI model asset, using built-in model");
+                ai_controls.set_base_model(models.add(AIModel::builtin()));
             }
+        } else {
+            warn!("Failed to load AI model asset, using the default model");
+            ai_controls.model = ai_controls.base_model();
         }
-
-        best
     }
+}
 
-    fn init_random(&mut self) {
-        let mut random_agents: Vec<Agent> = Vec::with_capacity(self.pop_size);
-        for _ in 0..self.pop_size {
-            random_agents.push(Agent {
-                net: NeuralNetwork::new(),
-                score: 0,
-                fitness: 0.0,
-            });
-        }
-
-        self.agents = random_agents;
-    }
+#[cfg(test)]
+mod tests {
+    use bevy::asset::HandleId;
 
-    fn calculate_fitness(&mut self) {
-        let mut score_sum = 0;
-        for mut agent in self.agents.iter_mut() {
-            agent.score = agent.net.score();
-            score_sum += agent.score;
-        }
+    use super::*;
 
-        for mut agent in self.agents.iter_mut() {
-            agent.fitness = agent.score as f32 / score_sum as f32;
-        }
+    fn handle() -> Handle<AIModel> {
+        Handle::weak(HandleId::random::<AIModel>())
     }
 
-    fn select_parent(&self) -> &Agent {
-        let mut selected_index = 0;
-        let mut r = rand::thread_rng().gen_range(0.01..=1.0);
-        while r > 0.0 {
-            r -= self.agents[selected_index].fitness;
-            selected_index += 1;
-        }
+    #[test]
+    fn new_base_model_replaces_the_base_model_in_use() {
+        let mut ai_controls = AIControls::new();
+        let (base, trained) = (handle(), handle());
 
-        selected_index -= 1;
-        &self.agents[selected_index]
+        ai_controls.set_base_model(base.clone());
+        assert_eq!(ai_controls.model, base);
+        ai_controls.set_base_model(trained.clone());
+        assert_eq!(ai_controls.model, trained);
     }
 
-    fn new_generation(&mut self) {
-        let mut new_agents: Vec<Agent> = Vec::with_capacity(self.pop_size);
-        while new_agents.len() < self.pop_size - 1 {
-            let p1 = self.select_parent();
-            let p2 = self.select_parent();
-            let mut child = p1.clone();
-            let alpha = rand::thread_rng().gen_range(0.0..=1.0);
-
-            child.net.crossover(&p2.net, alpha);
-            child.net.mutate(self.learning_rate);
-
-            new_agents.push(child);
-        }
+    #[test]
+    fn new_base_model_keeps_a_difficulty_model() {
+        let mut ai_controls = AIControls::new();
+        let (base, hard, trained) = (handle(), handle(), handle());
 
-        self.agents = new_agents;
+        ai_controls.set_base_model(base);
+        ai_controls.use_model(hard.clone());
+        ai_controls.set_base_model(trained.clone());
+        assert_eq!(ai_controls.model, hard);
+        assert_eq!(ai_controls.base_model(), trained);
     }
 }
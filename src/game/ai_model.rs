@@ -0,0 +1,456 @@
+use std::{
+    env,
+    error::Error,
+    fmt, fs, io,
+    path::{Path, PathBuf},
+    str::{self, Utf8Error},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use bevy::{
+    asset::{AssetLoader, FileAssetIo, LoadContext, LoadedAsset},
+    reflect::TypeUuid,
+    time::{Timer, TimerMode},
+    utils::BoxedFuture,
+};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    genetic_algorithm::GeneticAlgorithmSettings,
+    neural_network::{Activation, NeuralNetwork, OUTPUTS_COUNT},
+    observation::ObservationFeature,
+};
+
+pub const MODEL_FORMAT_VERSION: u32 = 1;
+
+pub const MODEL_PATH_ARG: &str = "--model";
+pub const MODEL_PATH_ENV_VAR: &str = "ARCHE_MODEL";
+
+const ASSET_FOLDER: &str = "assets";
+const MODEL_EXTENSION: &str = "model.json";
+const MODEL_ASSET_PATH: &str = "ai/net.model.json";
+const USER_DATA_MODEL_PATH: &str = "arche_rs/ai/net.model.json";
+const LEGACY_MODEL_ASSET_PATH: &str = "ai/net.txt";
+const LEGACY_USER_DATA_MODEL_PATH: &str = "arche_rs/ai/net.txt";
+const MODEL_POLL_INTERVAL: f32 = 1.0;
+
+const BUILTIN_MODEL: &str = include_str!("../../assets/ai/net.model.json");
+
+#[derive(Debug)]
+pub enum ModelError {
+    Io(io::Error),
+    Encoding(Utf8Error),
+    Parse(serde_json::Error),
+    UnsupportedVersion(u32),
+    Malformed(String),
+    Metadata(&'static str),
+    Dimensions {
+        expected_inputs: usize,
+        expected_outputs: usize,
+        inputs: usize,
+        outputs: usize,
+    },
+}
+
+impl fmt::Display for ModelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ModelError::Io(error) => write!(f, "failed to access model file: {error}"),
+            ModelError::Encoding(error) => write!(f, "failed to parse model: {error}"),
+            ModelError::Parse(error) => write!(f, "failed to parse model: {error}"),
+            ModelError::UnsupportedVersion(version) => write!(
+                f,
+                "model format version {version} is newer than supported version {MODEL_FORMAT_VERSION}"
+            ),
+            ModelError::Malformed(reason) => write!(f, "malformed network: {reason}"),
+            ModelError::Metadata(field) => {
+                write!(f, "model metadata {field} don't match the network")
+            }
+            ModelError::Dimensions {
+                expected_inputs,
+                expected_outputs,
+                inputs,
+                outputs,
+            } => write!(
+                f,
+                "network has {inputs} inputs and {outputs} outputs, expected {expected_inputs} and {expected_outputs}"
+            ),
+        }
+    }
+}
+
+impl Error for ModelError {}
+
+impl From<io::Error> for ModelError {
+    fn from(error: io::Error) -> Self {
+        ModelError::Io(error)
+    }
+}
+
+impl From<serde_json::Error> for ModelError {
+    fn from(error: serde_json::Error) -> Self {
+        ModelError::Parse(error)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct TrainingMetadata {
+    pub settings: GeneticAlgorithmSettings,
+    pub generations: u32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ModelMetadata {
+    #[serde(default = "ObservationFeature::defaults")]
+    pub inputs: Vec<ObservationFeature>,
+    pub layer_sizes: Vec<usize>,
+    pub activations: Vec<Activation>,
+    pub training: Option<TrainingMetadata>,
+    pub score: i32,
+    pub created_at: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, TypeUuid)]
+#[uuid = "3c2f6c1e-8a4d-4b7e-9d55-0f1a2b6e7c41"]
+pub struct AIModel {
+    pub version: u32,
+    pub metadata: ModelMetadata,
+    pub net: NeuralNetwork,
+}
+
+#[derive(Deserialize)]
+struct VersionProbe {
+    version: Option<u32>,
+}
+
+impl AIModel {
+    pub fn new(net: NeuralNetwork, training: Option<TrainingMetadata>) -> Self {
+        let created_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_secs());
+
+        Self {
+            version: MODEL_FORMAT_VERSION,
+            metadata: ModelMetadata {
+                inputs: net.inputs().to_vec(),
+                layer_sizes: net.layer_sizes(),
+                activations: net.activations(),
+                training,
+                score: net.score(),
+                created_at,
+            },
+            net,
+        }
+    }
+
+    pub fn builtin() -> Self {
+        AIModel::from_json(BUILTIN_MODEL).expect("Built-in model is invalid!")
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, ModelError> {
+        let serialized = fs::read_to_string(path)?;
+        AIModel::from_json(&serialized)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ModelError> {
+        let serialized = str::from_utf8(bytes).map_err(ModelError::Encoding)?;
+        AIModel::from_json(serialized)
+    }
+
+    pub fn from_json(serialized: &str) -> Result<Self, ModelError> {
+        let probe: VersionProbe = serde_json::from_str(serialized)?;
+        let mut model = match probe.version {
+            Some(version) if version > MODEL_FORMAT_VERSION => {
+                return Err(ModelError::UnsupportedVersion(version));
+            }
+            Some(_) => serde_json::from_str(serialized)?,
+            None => {
+                let net: NeuralNetwork = serde_json::from_str(serialized)?;
+                Self {
+                    version: 0,
+                    metadata: ModelMetadata {
+                        inputs: net.inputs().to_vec(),
+                        layer_sizes: net.layer_sizes(),
+                        activations: net.activations(),
+                        training: None,
+                        score: 0,
+                        created_at: 0,
+                    },
+                    net,
+                }
+            }
+        };
+
+        model.validate()?;
+        if model.version == 0 {
+            model.metadata.score = model.net.score();
+        }
+        Ok(model)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), ModelError> {
+        let serialized = serde_json::to_string(self)?;
+        fs::write(path, serialized)?;
+        Ok(())
+    }
+
+    pub fn validate(&self) -> Result<(), ModelError> {
+        self.net.validate().map_err(ModelError::Malformed)?;
+
+        let inputs = self.net.inputs_count();
+        let outputs = self.net.outputs_count();
+        if inputs != self.net.inputs().len() || outputs != OUTPUTS_COUNT {
+            return Err(ModelError::Dimensions {
+                expected_inputs: self.net.inputs().len(),
+                expected_outputs: OUTPUTS_COUNT,
+                inputs,
+                outputs,
+            });
+        }
+
+        if self.metadata.inputs != self.net.inputs() {
+            return Err(ModelError::Metadata("inputs"));
+        }
+        if self.metadata.layer_sizes != self.net.layer_sizes() {
+            return Err(ModelError::Metadata("layer sizes"));
+        }
+        if self.metadata.activations != self.net.activations() {
+            return Err(ModelError::Metadata("activations"));
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+pub struct AIModelLoader;
+
+impl AssetLoader for AIModelLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let model = AIModel::from_bytes(bytes)?;
+            load_context.set_default_asset(LoadedAsset::new(model));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &[MODEL_EXTENSION]
+    }
+}
+
+pub struct ModelLocation {
+    pub primary: PathBuf,
+    pub user_data: Option<PathBuf>,
+    legacy: Vec<PathBuf>,
+}
+
+impl ModelLocation {
+    pub fn resolve() -> Self {
+        let primary = arg_value(env::args(), MODEL_PATH_ARG)
+            .map(PathBuf::from)
+            .or_else(|| env::var_os(MODEL_PATH_ENV_VAR).map(PathBuf::from))
+            .unwrap_or_else(|| ModelLocation::asset_root().join(MODEL_ASSET_PATH));
+
+        let data_dir = dirs::data_dir();
+        let mut legacy = vec![ModelLocation::asset_root().join(LEGACY_MODEL_ASSET_PATH)];
+        legacy.extend(
+            data_dir
+                .as_ref()
+                .map(|dir| dir.join(LEGACY_USER_DATA_MODEL_PATH)),
+        );
+
+        Self {
+            primary,
+            user_data: data_dir.map(|dir| dir.join(USER_DATA_MODEL_PATH)),
+            legacy,
+        }
+    }
+
+    pub fn asset_root() -> PathBuf {
+        FileAssetIo::get_base_path().join(ASSET_FOLDER)
+    }
+
+    pub fn asset_path(path: &Path) -> Option<PathBuf> {
+        let file_name = path.file_name()?.to_str()?.to_lowercase();
+        if !file_name.ends_with(&format!(".{MODEL_EXTENSION}")) {
+            return None;
+        }
+
+        let path = path.canonicalize().ok()?;
+        let asset_root = ModelLocation::asset_root().canonicalize().ok()?;
+        path.strip_prefix(asset_root).ok().map(Path::to_path_buf)
+    }
+
+    pub fn existing(&self) -> Option<&Path> {
+        if self.primary.is_file() {
+            return Some(&self.primary);
+        }
+
+        self.user_data
+            .iter()
+            .chain(self.legacy.iter())
+            .map(PathBuf::as_path)
+            .find(|path| path.is_file())
+    }
+
+    pub fn save(&self, model: &AIModel) -> Result<PathBuf, ModelError> {
+        let primary_error = match model.save(&self.primary) {
+            Ok(()) => return Ok(self.primary.clone()),
+            Err(error) => error,
+        };
+
+        let Some(user_data) = &self.user_data else {
+            return Err(primary_error);
+        };
+
+        if let Some(parent) = user_data.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        model.save(user_data)?;
+        Ok(user_data.clone())
+    }
+}
+
+pub struct ModelFileWatcher {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    timer: Timer,
+}
+
+impl ModelFileWatcher {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            modified: ModelFileWatcher::modified_time(&path),
+            path,
+            timer: Timer::from_seconds(MODEL_POLL_INTERVAL, TimerMode::Repeating),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn poll(&mut self, delta: Duration) -> bool {
+        if !self.timer.tick(delta).just_finished() {
+            return false;
+        }
+
+        let modified = ModelFileWatcher::modified_time(&self.path);
+        if modified.is_none() || modified == self.modified {
+            return false;
+        }
+
+        self.modified = modified;
+        true
+    }
+
+    fn modified_time(path: &Path) -> Option<SystemTime> {
+        fs::metadata(path)
+            .and_then(|metadata| metadata.modified())
+            .ok()
+    }
+}
+
+pub fn arg_value<I: Iterator<Item = String>>(mut args: I, name: &str) -> Option<String> {
+    while let Some(arg) = args.next() {
+        if arg == name {
+            return args.next();
+        }
+
+        if let Some(value) = arg
+            .strip_prefix(name)
+            .and_then(|rest| rest.strip_prefix('='))
+        {
+            return Some(value.to_string());
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn legacy_networks_load_as_version_zero() {
+        let model = AIModel::from_json(BUILTIN_MODEL).unwrap();
+        assert_eq!(model.version, 0);
+        assert_eq!(model.metadata.inputs, ObservationFeature::DEFAULT.to_vec());
+        assert_eq!(model.metadata.layer_sizes, vec![2, 3, 2]);
+        assert!(model.metadata.training.is_none());
+    }
+
+    #[test]
+    fn saved_models_keep_the_network() {
+        let legacy = AIModel::from_json(BUILTIN_MODEL).unwrap();
+        let serialized = serde_json::to_string(&AIModel::new(legacy.net.clone(), None)).unwrap();
+        let model = AIModel::from_json(&serialized).unwrap();
+        assert_eq!(model.version, MODEL_FORMAT_VERSION);
+
+        let input = vec![0.25, 0.75];
+        assert_eq!(
+            model.net.calculate_output(input.clone()),
+            legacy.net.calculate_output(input)
+        );
+    }
+
+    #[test]
+    fn newer_models_are_rejected() {
+        let serialized = format!("{{\"version\":{}}}", MODEL_FORMAT_VERSION + 1);
+        assert!(matches!(
+            AIModel::from_json(&serialized),
+            Err(ModelError::UnsupportedVersion(version)) if version == MODEL_FORMAT_VERSION + 1
+        ));
+    }
+
+    #[test]
+    fn metadata_must_match_the_network() {
+        let model = AIModel::new(AIModel::builtin().net, None);
+        for (field, value) in [
+            ("inputs", serde_json::json!(["SelfHeight", "Turn"])),
+            ("layer_sizes", serde_json::json!([2, 4, 2])),
+            ("activations", serde_json::json!(["Relu", "Relu"])),
+        ] {
+            let mut serialized = serde_json::to_value(&model).unwrap();
+            serialized["metadata"][field] = value;
+            assert!(matches!(
+                AIModel::from_json(&serialized.to_string()),
+                Err(ModelError::Metadata(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn invalid_utf8_is_a_parse_error() {
+        assert!(matches!(
+            AIModel::from_bytes(&[b'{', 0xff, b'}']),
+            Err(ModelError::Encoding(_))
+        ));
+    }
+
+    #[test]
+    fn only_model_files_load_as_assets() {
+        let root = ModelLocation::asset_root();
+        assert_eq!(
+            ModelLocation::asset_path(&root.join(MODEL_ASSET_PATH)),
+            Some(PathBuf::from(MODEL_ASSET_PATH))
+        );
+        assert_eq!(
+            ModelLocation::asset_path(&root.join(LEGACY_MODEL_ASSET_PATH)),
+            None
+        );
+        assert_eq!(AIModelLoader.extensions(), [MODEL_EXTENSION]);
+    }
+
+    #[test]
+    fn mismatched_inputs_are_rejected() {
+        let serialized = BUILTIN_MODEL.replacen('{', "{\"inputs\":[\"SelfHeight\"],", 1);
+        assert!(AIModel::from_json(&serialized).is_err());
+    }
+}
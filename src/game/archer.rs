@@ -4,9 +4,17 @@ use bevy::{prelude::*, sprite::Anchor};
 use rand::Rng;
 
 use super::{
-    ai_controls::AIControls, animation::Animation, animation::AnimationMode, arrow::Arrow,
-    collision::RectCollider, player_controls::PlayerControls, GameStage, GameStageSpawned,
-    GameState, GameTextures, GameTurn, DIFFICULTY, ROT_AXIS_Z,
+    ai_controls::AIControls,
+    animation::Animation,
+    animation::AnimationMode,
+    arena::ArenaMode,
+    arrow::{Arrow, ARROW_POWER_SCALE},
+    difficulty::{AdaptiveDifficulty, Difficulty},
+    geometry::{self, Hitbox},
+    neural_network::HitZone,
+    observation::Observation,
+    player_controls::PlayerControls,
+    GameRandom, GameStage, GameStageSpawned, GameState, GameTextures, GameTurn, ROT_AXIS_Z,
 };
 
 pub struct ArcherPlugin;
@@ -15,7 +23,12 @@ impl Plugin for ArcherPlugin {
     fn build(&self, app: &mut App) {
         app.add_system(player_archer_update_system)
             .add_system(enemy_archer_update_system)
-            .add_system(shoot_ai_update_system.after(enemy_archer_update_system))
+            .add_system(arena_player_archer_update_system)
+            .add_system(
+                shoot_ai_update_system
+                    .after(enemy_archer_update_system)
+                    .after(arena_player_archer_update_system),
+            )
             .add_system(archers_visibility_update_system)
             .add_system(archers_look_at_target_update_system)
             .add_system(archers_react_to_pull_update_system)
@@ -49,6 +62,17 @@ impl Archer {
             flipped,
         }
     }
+
+    pub fn draw(&mut self, angle: f32, power: f32) {
+        self.is_active = true;
+        self.is_combat = true;
+        self.pull_angle = angle;
+        self.pull_power = power;
+    }
+
+    pub fn release(&mut self) {
+        self.shoot_arrow = true;
+    }
 }
 
 #[derive(Component)]
@@ -103,13 +127,24 @@ struct ArrowTrajectoryReceiver;
 
 #[derive(Component)]
 pub struct DamageReceiver {
+    pub zone: HitZone,
     pub hitpoints: i32,
 }
 
+impl DamageReceiver {
+    pub fn new(zone: HitZone) -> Self {
+        Self {
+            zone,
+            hitpoints: zone.damage(),
+        }
+    }
+}
+
 pub fn spawn_archer(
     commands: &mut Commands,
     game_textures: &GameTextures,
     parent_archer: Entity,
+    flipped: bool,
     receive_trajectory: bool,
 ) {
     let archer_idle = commands
@@ -131,62 +166,21 @@ pub fn spawn_archer(
         })
         .id();
 
-    let head_hitbox = commands
-        .spawn(TransformBundle {
-            local: Transform::from_translation(Vec3::new(0.0, 2.8, 0.0)),
-            ..default()
-        })
-        .insert(ArcherComponent {
-            parent: parent_archer,
-        })
-        .insert(RectCollider::new(
-            parent_archer.into(),
-            Vec2::ZERO,
-            0.7,
-            0.7,
-        ))
-        .insert(DamageReceiver { hitpoints: 4 })
-        .id();
-
-    commands.entity(parent_archer).add_child(head_hitbox);
-
-    let body_hitbox = commands
-        .spawn(TransformBundle {
-            local: Transform::from_translation(Vec3::new(0.0, 1.9, 0.0)),
-            ..default()
-        })
-        .insert(ArcherComponent {
-            parent: parent_archer,
-        })
-        .insert(RectCollider::new(
-            parent_archer.into(),
-            Vec2::ZERO,
-            0.8,
-            1.0,
-        ))
-        .insert(DamageReceiver { hitpoints: 2 })
-        .id();
-
-    commands.entity(parent_archer).add_child(body_hitbox);
-
-    let legs_hitbox = commands
-        .spawn(TransformBundle {
-            local: Transform::from_translation(Vec3::new(0.0, 0.7, 0.0)),
-            ..default()
-        })
-        .insert(ArcherComponent {
-            parent: parent_archer,
-        })
-        .insert(RectCollider::new(
-            parent_archer.into(),
-            Vec2::ZERO,
-            0.8,
-            1.2,
-        ))
-        .insert(DamageReceiver { hitpoints: 2 })
-        .id();
-
-    commands.entity(parent_archer).add_child(legs_hitbox);
+    for hitbox in Hitbox::ALL {
+        let hitbox_entity = commands
+            .spawn(TransformBundle {
+                local: hitbox.transform(),
+                ..default()
+            })
+            .insert(ArcherComponent {
+                parent: parent_archer,
+            })
+            .insert(hitbox.collider(parent_archer.into(), flipped))
+            .insert(DamageReceiver::new(hitbox.zone))
+            .id();
+
+        commands.entity(parent_archer).add_child(hitbox_entity);
+    }
 
     let mut shooting_point: Option<Entity> = None;
     let archer_combat = commands
@@ -197,7 +191,7 @@ pub fn spawn_archer(
                 anchor: Anchor::BottomCenter,
                 ..default()
             },
-            transform: Transform::from_translation(Vec3::new(0.0, 0.0, 0.2)),
+            transform: geometry::combat_transform(),
             visibility: Visibility::INVISIBLE,
             ..default()
         })
@@ -229,7 +223,7 @@ pub fn spawn_archer(
                         custom_size: Vec2::new(4.0, 4.0).into(),
                         ..default()
                     },
-                    transform: Transform::from_translation(Vec3::new(0.3, 2.1, -0.1)),
+                    transform: geometry::arm_transform(),
                     visibility: Visibility::INVISIBLE,
                     ..default()
                 })
@@ -246,7 +240,7 @@ pub fn spawn_archer(
                                 custom_size: Vec2::new(4.0, 4.0).into(),
                                 ..default()
                             },
-                            transform: Transform::from_translation(Vec3::new(1.3, 0.0, 0.2)),
+                            transform: geometry::bow_transform(),
                             visibility: Visibility::INVISIBLE,
                             ..default()
                         })
@@ -268,7 +262,7 @@ pub fn spawn_archer(
 
                             shooting_point = parent
                                 .spawn(TransformBundle {
-                                    local: Transform::from_translation(Vec3::new(1.25, 0.0, 0.0)),
+                                    local: geometry::shooting_point_transform(),
                                     ..default()
                                 })
                                 .insert(ShootingPoint)
@@ -288,7 +282,11 @@ pub fn spawn_archer(
                         custom_size: Vec2::new(4.0, 4.0).into(),
                         ..default()
                     },
-                    transform: Transform::from_translation(Vec3::new(-0.45, 2.1, 0.2)),
+                    transform: Transform::from_translation(Vec3::new(
+                        -0.45,
+                        geometry::ARM_OFFSET.y,
+                        0.2,
+                    )),
                     ..default()
                 })
                 .insert(LookAtBow)
@@ -314,7 +312,7 @@ pub fn spawn_archer(
 fn player_archer_update_system(
     mut game_state: ResMut<GameState>,
     mut player_controls: ResMut<PlayerControls>,
-    mut archers: Query<&mut Archer, With<ArcherPlayer>>,
+    mut archers: Query<&mut Archer, (With<ArcherPlayer>, Without<ShootAI>)>,
 ) {
     for mut archer in archers.iter_mut() {
         if game_state.waiting_for_hit || !player_controls.enabled() {
@@ -365,9 +363,12 @@ fn enemy_archer_update_system(
     mut commands: Commands,
     game_state: Res<GameState>,
     mut ai_controls: ResMut<AIControls>,
+    mut random: ResMut<GameRandom>,
+    difficulty: Res<Difficulty>,
+    adaptive_difficulty: Res<AdaptiveDifficulty>,
     mut archers: Query<(Entity, &mut Archer), With<ArcherEnemy>>,
 ) {
-    if !ai_controls.enabled() {
+    if !ai_controls.enabled() || !ai_controls.ready() {
         return;
     }
 
@@ -388,14 +389,59 @@ fn enemy_archer_update_system(
         let mut angle = ai_controls.get_pull_angle();
         let mut pull = ai_controls.get_pull_power();
 
-        angle += rand::thread_rng().gen_range(-1.0..=1.0) * (1.0 - DIFFICULTY) * 0.1;
-        pull += rand::thread_rng().gen_range(-1.0..=1.0) * (1.0 - DIFFICULTY) * 0.1;
+        angle += random.gen_range(-1.0..=1.0) * adaptive_difficulty.aim_noise(*difficulty);
+        pull += random.gen_range(-1.0..=1.0) * adaptive_difficulty.aim_noise(*difficulty);
 
         commands.entity(entity).remove::<ShootAI>();
         commands.entity(entity).insert(ShootAI {
             angle,
             power: pull,
-            time: 1.0,
+            time: difficulty.wind_up_time(),
+            current_time: 0.0,
+        });
+    }
+}
+
+fn arena_player_archer_update_system(
+    mut commands: Commands,
+    game_state: Res<GameState>,
+    arena_mode: Option<ResMut<ArenaMode>>,
+    mut player_controls: ResMut<PlayerControls>,
+    mut random: ResMut<GameRandom>,
+    difficulty: Res<Difficulty>,
+    mut archers: Query<(Entity, &mut Archer), With<ArcherPlayer>>,
+) {
+    let Some(mut arena_mode) = arena_mode else {
+        return;
+    };
+
+    if !player_controls.enabled()
+        || game_state.waiting_for_hit
+        || game_state.turn != GameTurn::Player
+        || game_state.stage != GameStage::Playing
+    {
+        return;
+    }
+
+    for (entity, mut archer) in archers.iter_mut() {
+        player_controls.set_enabled(false);
+
+        archer.is_active = true;
+        archer.is_combat = true;
+
+        let observation = Observation::new(&game_state, GameTurn::Player);
+        let (mut angle, mut pull) = arena_mode
+            .shoot(&observation)
+            .map_or((0.0, 0.0), |shot| (shot.angle, shot.power));
+
+        angle += random.gen_range(-1.0..=1.0) * difficulty.aim_noise();
+        pull += random.gen_range(-1.0..=1.0) * difficulty.aim_noise();
+
+        commands.entity(entity).remove::<ShootAI>();
+        commands.entity(entity).insert(ShootAI {
+            angle,
+            power: pull,
+            time: difficulty.wind_up_time(),
             current_time: 0.0,
         });
     }
@@ -405,25 +451,22 @@ fn shoot_ai_update_system(
     mut commands: Commands,
     mut game_state: ResMut<GameState>,
     time: Res<Time>,
-    mut archers: Query<(Entity, &mut ShootAI, &mut Archer), With<ArcherEnemy>>,
+    mut archers: Query<(Entity, &mut ShootAI, &mut Archer)>,
 ) {
     for (entity, mut shoot_ai, mut archer) in archers.iter_mut() {
         game_state.waiting_for_hit = true;
         shoot_ai.current_time += time.delta_seconds();
 
         if shoot_ai.current_time > shoot_ai.time {
-            archer.pull_angle = shoot_ai.angle;
-            archer.pull_power = shoot_ai.power;
-            archer.shoot_arrow = true;
+            archer.draw(shoot_ai.angle, shoot_ai.power);
+            archer.release();
             commands.entity(entity).remove::<ShootAI>();
         } else {
             let angle_delta =
                 shoot_ai.angle * (shoot_ai.current_time + shoot_ai.time * 0.3).clamp(0.0, 1.0);
-            archer.pull_angle = angle_delta;
-
             let power_delta =
                 shoot_ai.power * (shoot_ai.current_time + shoot_ai.time * 0.3).clamp(0.0, 1.0);
-            archer.pull_power = power_delta;
+            archer.draw(angle_delta, power_delta);
         }
     }
 }
@@ -527,7 +570,7 @@ fn archer_shooting_system(
                 archer.shoot_arrow = false;
                 let translation = transform.translation();
                 let start_pos = Vec2::new(translation.x, translation.y);
-                let arrow_velocity = archer.pull_power * 10.0;
+                let arrow_velocity = archer.pull_power * ARROW_POWER_SCALE;
                 let mut arrow_angle = archer.pull_angle;
                 if archer.flipped {
                     arrow_angle -= PI;
@@ -545,9 +588,9 @@ fn archer_shooting_system(
                             .with_rotation(Quat::from_rotation_z(arrow_angle)),
                         ..default()
                     })
-                    .insert(Arrow::new(entity, start_pos, arrow_velocity, arrow_angle))
+                    .insert(Arrow::new(start_pos, arrow_velocity, arrow_angle))
                     .insert(GameStageSpawned)
-                    .insert(RectCollider::new(entity.into(), Vec2::ZERO, 0.3, 0.3));
+                    .insert(geometry::arrow_collider(entity.into(), archer.flipped));
 
                 archer.is_active = false;
                 archer.is_combat = false;
@@ -602,7 +645,7 @@ fn trajectory_system(
 
             transform.translation = receiver_transform.translation();
             trajectory.angle = archer.pull_angle;
-            trajectory.power = archer.pull_power * 10.0;
+            trajectory.power = archer.pull_power * ARROW_POWER_SCALE;
         }
     } else {
         visibility.is_visible = false;
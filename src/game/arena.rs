@@ -0,0 +1,342 @@
+use std::{env, fmt, path::PathBuf, str::FromStr};
+
+use bevy::{app::AppExit, prelude::*};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use super::{
+    ai_controls::AIControls,
+    ai_model::{arg_value, AIModel, ModelError},
+    difficulty::AdaptiveDifficulty,
+    neural_network::{HitCounts, NeuralNetwork},
+    observation::Observation,
+    strategy::{Shot, Strategy, StrategyKind},
+    GameRandom, GameStage, GameState, GameTurn, ShotRecord,
+};
+
+pub const ARENA_MATCHES_ARG: &str = "--arena";
+pub const ARENA_PLAYER_ARG: &str = "--arena-player";
+pub const MAX_TURNS: usize = 200;
+
+pub struct ArenaPlugin;
+
+impl Plugin for ArenaPlugin {
+    fn build(&self, app: &mut App) {
+        match ArenaMode::from_args() {
+            Ok(Some(arena_mode)) => {
+                let mut adaptive_difficulty = AdaptiveDifficulty::default();
+                adaptive_difficulty.enabled = false;
+                app.insert_resource(arena_mode)
+                    .insert_resource(adaptive_difficulty)
+                    .add_system(arena_stage_update_system);
+            }
+            Ok(None) => {}
+            Err(error) => error!("Failed to start the arena: {error}"),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ArenaSide {
+    pub kind: StrategyKind,
+    pub model: Option<PathBuf>,
+}
+
+impl ArenaSide {
+    pub fn load_network(&self) -> Result<Option<NeuralNetwork>, ModelError> {
+        match (&self.kind, &self.model) {
+            (StrategyKind::Network, Some(path)) => Ok(Some(AIModel::load(path)?.net)),
+            (StrategyKind::Network, None) => Ok(Some(AIModel::builtin().net)),
+            _ => Ok(None),
+        }
+    }
+}
+
+impl FromStr for ArenaSide {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let mut parts = value.splitn(2, ':');
+        let kind: StrategyKind = parts.next().unwrap_or_default().parse()?;
+        let model = parts.next().map(PathBuf::from);
+        if model.is_some() && kind != StrategyKind::Network {
+            return Err(format!(
+                "Only the network strategy takes a model, got '{value}'"
+            ));
+        }
+
+        Ok(Self { kind, model })
+    }
+}
+
+#[derive(Clone, Default, Debug)]
+pub struct ArenaReport {
+    pub matches: u32,
+    pub player_wins: u32,
+    pub enemy_wins: u32,
+    pub draws: u32,
+    pub turns: u32,
+    pub player_hits: HitCounts,
+    pub enemy_hits: HitCounts,
+}
+
+impl ArenaReport {
+    pub fn record(&mut self, winner: Option<GameTurn>, shots: &[ShotRecord]) {
+        self.matches += 1;
+        match winner {
+            Some(GameTurn::Player) => self.player_wins += 1,
+            Some(GameTurn::Enemy) => self.enemy_wins += 1,
+            None => self.draws += 1,
+        }
+
+        self.turns += shots.len() as u32;
+        for shot in shots {
+            match shot.shooter {
+                GameTurn::Player => self.player_hits.add(shot.zone),
+                GameTurn::Enemy => self.enemy_hits.add(shot.zone),
+            }
+        }
+    }
+
+    pub fn win_rate(&self, side: GameTurn) -> f32 {
+        let wins = match side {
+            GameTurn::Player => self.player_wins,
+            GameTurn::Enemy => self.enemy_wins,
+        };
+        wins as f32 / self.matches.max(1) as f32
+    }
+
+    pub fn average_turns(&self) -> f32 {
+        self.turns as f32 / self.matches.max(1) as f32
+    }
+}
+
+impl fmt::Display for ArenaReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Matches: {}", self.matches)?;
+        writeln!(
+            f,
+            "Player wins: {} ({:.1}%)",
+            self.player_wins,
+            self.win_rate(GameTurn::Player) * 100.0
+        )?;
+        writeln!(
+            f,
+            "Enemy wins:  {} ({:.1}%)",
+            self.enemy_wins,
+            self.win_rate(GameTurn::Enemy) * 100.0
+        )?;
+        writeln!(f, "Draws:       {}", self.draws)?;
+        writeln!(f, "Average turns: {:.1}", self.average_turns())?;
+        for (name, hits) in [("Player", &self.player_hits), ("Enemy", &self.enemy_hits)] {
+            writeln!(
+                f,
+                "{name} hits: {} head, {} body, {} legs, {} miss",
+                hits.head, hits.body, hits.legs, hits.miss
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+pub struct Arena {
+    sides: [(StrategyKind, Option<NeuralNetwork>); 2],
+    aim_noise: f32,
+    random: StdRng,
+}
+
+impl Arena {
+    pub fn new(
+        player: &ArenaSide,
+        enemy: &ArenaSide,
+        aim_noise: f32,
+        seed: u64,
+    ) -> Result<Self, ModelError> {
+        Ok(Self {
+            sides: [
+                (player.kind, player.load_network()?),
+                (enemy.kind, enemy.load_network()?),
+            ],
+            aim_noise,
+            random: StdRng::seed_from_u64(seed),
+        })
+    }
+
+    /// Plays the matches with the sides taking turns to open, so neither keeps the first shot.
+    pub fn run<F: FnMut(&ArenaReport)>(&mut self, matches: u32, mut on_match: F) -> ArenaReport {
+        let mut report = ArenaReport::default();
+        let openers = [GameTurn::Player, GameTurn::Enemy].into_iter().cycle();
+        for opener in openers.take(matches as usize) {
+            let (winner, shots) = self.play_match(opener);
+            report.record(winner, &shots);
+            on_match(&report);
+        }
+
+        report
+    }
+
+    fn play_match(&mut self, opener: GameTurn) -> (Option<GameTurn>, Vec<ShotRecord>) {
+        let mut strategies: Vec<Box<dyn Strategy>> = self
+            .sides
+            .iter()
+            .map(|(kind, net)| kind.build(net.as_ref(), self.random.gen()))
+            .collect();
+
+        let mut game_state = GameState::new();
+        game_state.turn = opener;
+        game_state.turn_count = 0;
+        for _ in 0..MAX_TURNS {
+            game_state.player_height = self.random.gen_range(0.0..=1.0);
+            game_state.enemy_height = self.random.gen_range(0.0..=1.0);
+
+            let shooter = game_state.turn;
+            let strategy = match shooter {
+                GameTurn::Player => &mut strategies[0],
+                GameTurn::Enemy => &mut strategies[1],
+            };
+            let observation = Observation::new(&game_state, shooter);
+            let shot = strategy.shoot(&observation).unwrap_or(Shot {
+                power: 0.0,
+                angle: 0.0,
+            });
+            let power = shot.power + self.random.gen_range(-1.0..=1.0) * self.aim_noise;
+            let angle = shot.angle + self.random.gen_range(-1.0..=1.0) * self.aim_noise;
+
+            let (self_height, enemy_height) = (observation.self_height, observation.enemy_height);
+            let zone = NeuralNetwork::simulate_shot(power, angle, self_height, enemy_height);
+            if game_state.apply_shot(shooter, power, angle, zone) {
+                return (Some(shooter), game_state.shot_history);
+            }
+
+            game_state.next_turn();
+        }
+
+        (None, game_state.shot_history)
+    }
+}
+
+#[derive(Resource)]
+pub struct ArenaMode {
+    matches: u32,
+    player_kind: StrategyKind,
+    player_net: Option<NeuralNetwork>,
+    player: Box<dyn Strategy>,
+    report: ArenaReport,
+}
+
+impl ArenaMode {
+    pub fn from_args() -> Result<Option<Self>, String> {
+        let Some(matches) = arg_value(env::args(), ARENA_MATCHES_ARG) else {
+            return Ok(None);
+        };
+        let matches: u32 = matches
+            .parse()
+            .map_err(|_| format!("Invalid value '{matches}' for '{ARENA_MATCHES_ARG}'"))?;
+
+        let player: ArenaSide = arg_value(env::args(), ARENA_PLAYER_ARG)
+            .unwrap_or_else(|| "network".into())
+            .parse()?;
+        let player_net = player.load_network().map_err(|error| error.to_string())?;
+
+        Ok(Some(Self {
+            matches,
+            player_kind: player.kind,
+            player: player.kind.build(player_net.as_ref(), 0),
+            player_net,
+            report: ArenaReport::default(),
+        }))
+    }
+
+    pub fn shoot(&mut self, observation: &Observation) -> Option<Shot> {
+        self.player.shoot(observation)
+    }
+}
+
+fn arena_stage_update_system(
+    mut game_state: ResMut<GameState>,
+    mut arena_mode: ResMut<ArenaMode>,
+    ai_controls: Res<AIControls>,
+    mut random: ResMut<GameRandom>,
+    mut exit: EventWriter<AppExit>,
+    mut recorded: Local<bool>,
+) {
+    if game_state.needs_refresh || game_state.wait_for > 0.0 {
+        return;
+    }
+
+    let winner = match game_state.stage {
+        GameStage::Menu | GameStage::Credits => {
+            if ai_controls.ready() {
+                let seed = random.gen();
+                arena_mode.player = arena_mode
+                    .player_kind
+                    .build(arena_mode.player_net.as_ref(), seed);
+                game_state.stage = GameStage::StartGame;
+                game_state.needs_refresh = true;
+                *recorded = false;
+            }
+            return;
+        }
+        GameStage::Finished(winner) => Some(winner),
+        GameStage::Playing
+            if !game_state.waiting_for_hit && game_state.shot_history.len() >= MAX_TURNS =>
+        {
+            None
+        }
+        _ => return,
+    };
+
+    if *recorded {
+        return;
+    }
+    *recorded = true;
+
+    arena_mode.report.record(winner, &game_state.shot_history);
+    info!(
+        "Arena match {}/{}: winner {:?} after {} turns",
+        arena_mode.report.matches,
+        arena_mode.matches,
+        winner,
+        game_state.shot_history.len()
+    );
+
+    if arena_mode.report.matches >= arena_mode.matches {
+        println!("{}", arena_mode.report);
+        exit.send(AppExit);
+        return;
+    }
+
+    game_state.stage = GameStage::Menu;
+    game_state.needs_refresh = true;
+    game_state.wait_for = 1.0;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MATCHES: u32 = 20;
+
+    fn side(kind: StrategyKind) -> ArenaSide {
+        ArenaSide { kind, model: None }
+    }
+
+    fn run(seed: u64) -> ArenaReport {
+        let mut arena = Arena::new(
+            &side(StrategyKind::Solver),
+            &side(StrategyKind::Random),
+            0.0,
+            seed,
+        )
+        .unwrap();
+        arena.run(MATCHES, |_| {})
+    }
+
+    #[test]
+    fn seeded_runs_repeat_and_the_solver_wins() {
+        let report = run(3);
+        assert_eq!(format!("{report:?}"), format!("{:?}", run(3)));
+        assert_eq!(report.matches, MATCHES);
+        assert!(report.player_wins > MATCHES * 3 / 4, "{report:?}");
+    }
+}
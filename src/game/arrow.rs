@@ -1,18 +1,39 @@
-use bevy::prelude::*;
+use bevy::{prelude::*, time::FixedTimestep};
 
-use super::ROT_AXIS_Z;
+use super::{collision::RectCollider, ROT_AXIS_Z};
+
+pub const ARROW_GRAVITY: f32 = 9.0;
+pub const ARROW_WORLD_SCALE: f32 = 4.0;
+pub const ARROW_POWER_SCALE: f32 = 10.0;
+pub const ARROW_TIMESTEP: f32 = 0.02;
+pub const ARROW_STEP: f32 = ARROW_TIMESTEP * ARROW_TIME_SCALE;
+
+const ARROW_TIME_SCALE: f32 = 0.5;
 
 pub struct ArrowPlugin;
 
 impl Plugin for ArrowPlugin {
     fn build(&self, app: &mut App) {
-        app.add_system(arrow_update_system);
+        app.add_stage_after(
+            CoreStage::Update,
+            ArrowStage,
+            SystemStage::parallel()
+                .with_run_criteria(FixedTimestep::step(ARROW_TIMESTEP as f64))
+                .with_system(arrow_update_system.label(ArrowSystem::Move)),
+        );
     }
 }
 
+#[derive(StageLabel, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct ArrowStage;
+
+#[derive(SystemLabel, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum ArrowSystem {
+    Move,
+}
+
 #[derive(Component)]
 pub struct Arrow {
-    owner: Entity,
     is_moving: bool,
     start_pos: Vec2,
     velocity: f32,
@@ -21,9 +42,8 @@ pub struct Arrow {
 }
 
 impl Arrow {
-    pub fn new(owner: Entity, start_pos: Vec2, velocity: f32, angle: f32) -> Self {
+    pub fn new(start_pos: Vec2, velocity: f32, angle: f32) -> Self {
         Self {
-            owner,
             is_moving: true,
             start_pos,
             velocity,
@@ -43,28 +63,43 @@ impl Arrow {
         position
     }
 
+    pub fn pos_at_x(&self, x: f32) -> Option<Vec2> {
+        let horizontal_speed = self.velocity * f32::cos(self.angle) * ARROW_WORLD_SCALE;
+        let time = (x - self.start_pos.x) / horizontal_speed;
+        if !time.is_finite() || time < 0.0 {
+            return None;
+        }
+
+        Some(self.pos_at_time(time))
+    }
+
     pub fn get_trajectory(velocity: f32, angle: f32, t: f32) -> Vec2 {
         let mut x: f32 = velocity * t * f32::cos(angle);
         let mut y: f32 = velocity * t * f32::sin(angle);
-        y -= 0.5 * 9.0 * t * t;
-        x *= 4.0;
-        y *= 4.0;
+        y -= 0.5 * ARROW_GRAVITY * t * t;
+        x *= ARROW_WORLD_SCALE;
+        y *= ARROW_WORLD_SCALE;
         Vec2::new(x, y)
     }
 }
 
-fn arrow_update_system(time: Res<Time>, mut arrows: Query<(&mut Arrow, &mut Transform)>) {
-    for (mut arrow, mut transform) in arrows.iter_mut() {
+fn arrow_update_system(mut arrows: Query<(&mut Arrow, &mut Transform, Option<&mut RectCollider>)>) {
+    for (mut arrow, mut transform, collider) in arrows.iter_mut() {
         if !arrow.is_moving {
             continue;
         }
 
-        arrow.current_time += time.delta_seconds() * 0.5;
+        arrow.current_time += ARROW_STEP;
         let new_pos = arrow.pos_at_time(arrow.current_time);
         let new_translation = Vec3::new(new_pos.x, new_pos.y, transform.translation.z);
+        let previous_pos = transform.translation.truncate();
         let diff = new_translation - transform.translation;
         let angle = f32::atan2(diff.y, diff.x);
         transform.rotation = Quat::from_axis_angle(ROT_AXIS_Z, angle);
         transform.translation = new_translation;
+        if let Some(mut collider) = collider {
+            collider.set_motion(previous_pos, new_pos);
+            collider.set_rotation(angle);
+        }
     }
 }
@@ -0,0 +1,299 @@
+use std::f32::consts::PI;
+
+use bevy::prelude::*;
+use serde::Serialize;
+
+use super::{
+    arrow::{ARROW_GRAVITY, ARROW_POWER_SCALE, ARROW_WORLD_SCALE},
+    geometry::{self, Hitbox, ARCHER_X, ARM_OFFSET, CEILING, SHOOTING_REACH},
+    neural_network::{HitCounts, HitZone, NeuralNetwork},
+};
+
+pub const MIN_ANGLE: f32 = -80.0 * PI / 180.0;
+pub const MAX_ANGLE: f32 = 80.0 * PI / 180.0;
+pub const MIN_POWER: f32 = 0.0;
+pub const MAX_POWER: f32 = 1.0;
+
+const ANGLE_SAMPLES: usize = 160;
+const REFINE_ITERATIONS: usize = 32;
+
+#[derive(Serialize, Clone, Copy, PartialEq, Debug)]
+pub struct BallisticSolution {
+    pub angle: f32,
+    pub power: f32,
+}
+
+#[derive(Serialize, Clone, Copy, Debug)]
+pub struct LabeledShot {
+    pub self_height: f32,
+    pub enemy_height: f32,
+    pub power: f32,
+    pub angle: f32,
+}
+
+pub struct BallisticSolver {
+    pivot: Vec2,
+    reach: f32,
+}
+
+impl BallisticSolver {
+    pub fn new(pivot: Vec2, reach: f32) -> Self {
+        Self { pivot, reach }
+    }
+
+    pub fn for_archer(self_height: f32) -> Self {
+        let position = geometry::archer_position(false, self_height);
+        BallisticSolver::new(position + ARM_OFFSET, SHOOTING_REACH)
+    }
+
+    pub fn launch_point(&self, angle: f32) -> Vec2 {
+        self.pivot + Vec2::new(f32::cos(angle), f32::sin(angle)) * self.reach
+    }
+
+    pub fn required_power(&self, target: Vec2, angle: f32) -> Option<f32> {
+        let delta = target - self.launch_point(angle);
+        let cos = f32::cos(angle);
+        let rise = delta.x * f32::tan(angle) - delta.y;
+        if delta.x <= 0.0 || rise <= 0.0 {
+            return None;
+        }
+
+        let velocity_squared =
+            ARROW_GRAVITY * delta.x * delta.x / (2.0 * ARROW_WORLD_SCALE * cos * cos * rise);
+        let velocity = velocity_squared.sqrt();
+
+        let vertical_velocity = velocity * f32::sin(angle);
+        if vertical_velocity > 0.0 {
+            let apex_time = vertical_velocity / ARROW_GRAVITY;
+            let apex_x = ARROW_WORLD_SCALE * velocity * cos * apex_time;
+            let apex_height =
+                ARROW_WORLD_SCALE * vertical_velocity * vertical_velocity / (2.0 * ARROW_GRAVITY);
+            if apex_x < delta.x && self.launch_point(angle).y + apex_height >= CEILING {
+                return None;
+            }
+        }
+
+        Some(velocity / ARROW_POWER_SCALE)
+    }
+
+    pub fn solve(&self, target: Vec2) -> Vec<BallisticSolution> {
+        (0..=ANGLE_SAMPLES)
+            .map(sample_angle)
+            .filter_map(|angle| {
+                self.required_power(target, angle)
+                    .filter(|power| (MIN_POWER..=MAX_POWER).contains(power))
+                    .map(|power| BallisticSolution { angle, power })
+            })
+            .collect()
+    }
+
+    pub fn solve_for_power(&self, target: Vec2, power: f32) -> Vec<BallisticSolution> {
+        let error = |angle: f32| {
+            self.required_power(target, angle)
+                .map(|required| required - power)
+        };
+
+        let mut solutions = Vec::new();
+        for index in 0..ANGLE_SAMPLES {
+            let (mut low, mut high) = (sample_angle(index), sample_angle(index + 1));
+            let (low_error, high_error) = match (error(low), error(high)) {
+                (Some(low_error), Some(high_error)) => (low_error, high_error),
+                _ => continue,
+            };
+            if low_error.signum() == high_error.signum() {
+                continue;
+            }
+
+            for _ in 0..REFINE_ITERATIONS {
+                let middle = (low + high) * 0.5;
+                match error(middle) {
+                    Some(middle_error) if middle_error.signum() == low_error.signum() => {
+                        low = middle
+                    }
+                    _ => high = middle,
+                }
+            }
+
+            let angle = (low + high) * 0.5;
+            if let Some(power) = self.required_power(target, angle) {
+                solutions.push(BallisticSolution { angle, power });
+            }
+        }
+
+        solutions
+    }
+
+    pub fn best(&self, target: Vec2) -> Option<BallisticSolution> {
+        let solutions = self.solve(target);
+        let index = solutions
+            .iter()
+            .enumerate()
+            .min_by(|(_, a), (_, b)| a.power.total_cmp(&b.power))
+            .map(|(index, _)| index)?;
+
+        let power_at = |angle: f32| self.required_power(target, angle).unwrap_or(f32::MAX);
+        let mut low = solutions[index.saturating_sub(1)].angle;
+        let mut high = solutions[(index + 1).min(solutions.len() - 1)].angle;
+        for _ in 0..REFINE_ITERATIONS {
+            let left = low + (high - low) / 3.0;
+            let right = high - (high - low) / 3.0;
+            if power_at(left) < power_at(right) {
+                high = right;
+            } else {
+                low = left;
+            }
+        }
+
+        let angle = (low + high) * 0.5;
+        match self.required_power(target, angle) {
+            Some(power) if power <= solutions[index].power => {
+                Some(BallisticSolution { angle, power })
+            }
+            _ => Some(solutions[index]),
+        }
+    }
+}
+
+pub fn shot_offset(power: f32, angle: f32, self_height: f32, enemy_height: f32) -> f32 {
+    let launch_point = BallisticSolver::for_archer(self_height).launch_point(angle);
+    let target_y = Hitbox::HEAD
+        .center(geometry::archer_position(true, enemy_height))
+        .y;
+
+    let horizontal_speed = power * ARROW_POWER_SCALE * f32::cos(angle) * ARROW_WORLD_SCALE;
+    let time = (ARCHER_X - launch_point.x) / horizontal_speed;
+    if !time.is_finite() || time < 0.0 {
+        return launch_point.y - target_y;
+    }
+
+    let velocity = power * ARROW_POWER_SCALE;
+    let height =
+        ARROW_WORLD_SCALE * (velocity * time * f32::sin(angle) - 0.5 * ARROW_GRAVITY * time * time);
+    launch_point.y + height - target_y
+}
+
+pub fn target_point(enemy_height: f32, zone: HitZone) -> Option<Vec2> {
+    let hitbox = Hitbox::of(zone)?;
+    let center = hitbox.center(geometry::archer_position(true, enemy_height));
+    Some(center - Vec2::new(hitbox.shape.extends().x * 0.5, 0.0))
+}
+
+pub fn aim(self_height: f32, enemy_height: f32) -> Option<BallisticSolution> {
+    let target = target_point(enemy_height, HitZone::Head)?;
+    BallisticSolver::for_archer(self_height).best(target)
+}
+
+pub fn oracle_score() -> (i32, HitCounts) {
+    let mut score = 0;
+    let mut hits = HitCounts::default();
+    for i in 0..=10 {
+        let h_self = i as f32 * 0.1;
+        for j in 0..=10 {
+            let h_other = j as f32 * 0.1;
+            let hit_zone = match aim(h_self, h_other) {
+                Some(shot) => NeuralNetwork::simulate_shot(shot.power, shot.angle, h_self, h_other),
+                None => HitZone::Miss,
+            };
+            score += hit_zone.score();
+            hits.add(hit_zone);
+        }
+    }
+
+    (score, hits)
+}
+
+pub fn labeled_samples(steps: usize) -> Vec<LabeledShot> {
+    let height_at = |index: usize| index as f32 / (steps.max(2) - 1) as f32;
+
+    let mut samples = Vec::with_capacity(steps * steps);
+    for i in 0..steps {
+        let self_height = height_at(i);
+        for j in 0..steps {
+            let enemy_height = height_at(j);
+            if let Some(solution) = aim(self_height, enemy_height) {
+                samples.push(LabeledShot {
+                    self_height,
+                    enemy_height,
+                    power: solution.power,
+                    angle: solution.angle,
+                });
+            }
+        }
+    }
+
+    samples
+}
+
+fn sample_angle(index: usize) -> f32 {
+    MIN_ANGLE + (MAX_ANGLE - MIN_ANGLE) * index as f32 / ANGLE_SAMPLES as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::arrow::Arrow;
+
+    fn height_at_x(solver: &BallisticSolver, solution: BallisticSolution, x: f32) -> f32 {
+        let start = solver.launch_point(solution.angle);
+        let arrow = Arrow::new(start, solution.power * ARROW_POWER_SCALE, solution.angle);
+        arrow.pos_at_x(x).unwrap().y
+    }
+
+    #[test]
+    fn required_power_reaches_the_target() {
+        let solver = BallisticSolver::for_archer(0.3);
+        let target = target_point(0.8, HitZone::Head).unwrap();
+        let solutions = solver.solve(target);
+        assert!(!solutions.is_empty());
+        for solution in solutions {
+            assert!((height_at_x(&solver, solution, target.x) - target.y).abs() < 1e-2);
+        }
+    }
+
+    #[test]
+    fn targets_below_the_line_of_fire_need_no_power() {
+        let solver = BallisticSolver::for_archer(0.5);
+        let target = target_point(0.5, HitZone::Head).unwrap();
+        assert_eq!(solver.required_power(target, -0.5), None);
+        assert_eq!(solver.required_power(Vec2::new(-20.0, 0.0), 0.5), None);
+    }
+
+    #[test]
+    fn arcs_above_the_ceiling_are_rejected() {
+        let solver = BallisticSolver::for_archer(0.5);
+        let target = target_point(0.5, HitZone::Head).unwrap();
+        assert_eq!(solver.required_power(target, MAX_ANGLE), None);
+    }
+
+    #[test]
+    fn best_solution_uses_the_least_power() {
+        let solver = BallisticSolver::for_archer(0.0);
+        let target = target_point(1.0, HitZone::Head).unwrap();
+        let best = solver.best(target).unwrap();
+        assert!((MIN_ANGLE..=MAX_ANGLE).contains(&best.angle));
+        assert!(solver
+            .solve(target)
+            .iter()
+            .all(|solution| best.power <= solution.power + 1e-4));
+    }
+
+    #[test]
+    fn solve_for_power_keeps_the_requested_power() {
+        let solver = BallisticSolver::for_archer(0.5);
+        let target = target_point(0.5, HitZone::Head).unwrap();
+        let power = solver.best(target).unwrap().power + 0.1;
+        let solutions = solver.solve_for_power(target, power);
+        assert!(!solutions.is_empty());
+        for solution in solutions {
+            assert!((solution.power - power).abs() < 1e-3);
+            assert!((height_at_x(&solver, solution, target.x) - target.y).abs() < 1e-2);
+        }
+    }
+
+    #[test]
+    fn aim_hits_the_head_at_every_height() {
+        let (score, hits) = oracle_score();
+        assert_eq!(hits.head, 121);
+        assert_eq!(score, 121 * HitZone::Head.score());
+    }
+}
@@ -1,11 +1,31 @@
-use bevy::prelude::*;
+use std::collections::HashMap;
+
+use bevy::{
+    prelude::*,
+    sprite::{MaterialMesh2dBundle, Mesh2dHandle},
+};
+
+use super::arrow::{ArrowStage, ArrowSystem};
+
+const DEBUG_COLOR: Color = Color::rgba(0.0, 0.0, 1.0, 0.5);
+const DEBUG_HIT_COLOR: Color = Color::rgba(1.0, 0.0, 1.0, 0.5);
 
 pub struct CollisionPlugin;
 
 impl Plugin for CollisionPlugin {
     fn build(&self, app: &mut App) {
         app.insert_resource(ColliderSettings { show_debugs: false })
+            .init_resource::<CollisionPairs>()
+            .add_event::<CollisionStarted>()
+            .add_event::<CollisionEnded>()
+            .add_startup_system(setup_collider_debug_meshes)
             .add_system(colliders_position_update_system)
+            .add_system_to_stage(
+                ArrowStage,
+                collision_detection_system
+                    .label(CollisionSystem::Detect)
+                    .after(ArrowSystem::Move),
+            )
             .add_system(collider_debug_switch_system)
             .add_system(collider_added_debug_system)
             .add_system(collider_debug_update_system);
@@ -17,13 +37,151 @@ struct ColliderSettings {
     show_debugs: bool,
 }
 
-#[derive(Component)]
+#[derive(SystemLabel, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum CollisionSystem {
+    Detect,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct CollisionPair {
+    pub entity: Entity,
+    pub owner: Option<Entity>,
+    pub other: Entity,
+    pub other_owner: Option<Entity>,
+}
+
+impl CollisionPair {
+    pub fn other(&self, entity: Entity) -> Option<(Entity, Option<Entity>)> {
+        if self.entity == entity {
+            Some((self.other, self.other_owner))
+        } else if self.other == entity {
+            Some((self.entity, self.owner))
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct CollisionStarted {
+    pub pair: CollisionPair,
+    pub hit: SweepHit,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct CollisionEnded {
+    pub pair: CollisionPair,
+}
+
+#[derive(Resource, Default)]
+struct CollisionPairs {
+    active: HashMap<(Entity, Entity), CollisionPair>,
+}
+
+impl CollisionPairs {
+    fn touching(&self, entity: Entity) -> bool {
+        self.active
+            .values()
+            .any(|pair| pair.other(entity).is_some())
+    }
+}
+
+#[derive(Resource)]
+struct ColliderDebugMeshes {
+    quad: Handle<Mesh>,
+    circle: Handle<Mesh>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum CollisionLayer {
+    Arrow,
+    PlayerHitbox,
+    EnemyHitbox,
+    MenuButton,
+    Terrain,
+}
+
+impl CollisionLayer {
+    const fn bit(self) -> u32 {
+        1 << self as u32
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct CollisionMask(u32);
+
+impl CollisionMask {
+    pub const NONE: CollisionMask = CollisionMask(0);
+    pub const ALL: CollisionMask = CollisionMask(u32::MAX);
+
+    pub const fn with(self, layer: CollisionLayer) -> Self {
+        CollisionMask(self.0 | layer.bit())
+    }
+
+    pub const fn contains(self, layer: CollisionLayer) -> bool {
+        self.0 & layer.bit() != 0
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ColliderShape {
+    Aabb { half_extends: Vec2 },
+    OrientedBox { half_extends: Vec2 },
+    Circle { radius: f32 },
+}
+
+impl ColliderShape {
+    pub fn rect(width: f32, height: f32) -> Self {
+        ColliderShape::Aabb {
+            half_extends: Vec2::new(f32::abs(width) * 0.5, f32::abs(height) * 0.5),
+        }
+    }
+
+    pub fn oriented(width: f32, height: f32) -> Self {
+        ColliderShape::OrientedBox {
+            half_extends: Vec2::new(f32::abs(width) * 0.5, f32::abs(height) * 0.5),
+        }
+    }
+
+    pub fn circle(radius: f32) -> Self {
+        ColliderShape::Circle {
+            radius: f32::abs(radius),
+        }
+    }
+
+    pub fn extends(&self) -> Vec2 {
+        match *self {
+            ColliderShape::Aabb { half_extends } | ColliderShape::OrientedBox { half_extends } => {
+                half_extends
+            }
+            ColliderShape::Circle { radius } => Vec2::splat(radius),
+        }
+    }
+
+    pub fn size(&self) -> Vec2 {
+        self.extends() * 2.0
+    }
+}
+
+#[derive(Component, Clone, Copy, Debug)]
 pub struct RectCollider {
     pub owner: Option<Entity>,
     enabled: bool,
     center: Vec2,
+    previous_center: Vec2,
     offset: Vec2,
-    half_extends: Vec2,
+    rotation: f32,
+    shape: ColliderShape,
+    layer: CollisionLayer,
+    mask: CollisionMask,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct SweepHit {
+    pub time: f32,
+    pub position: Vec2,
+    pub point: Vec2,
+    pub normal: Vec2,
 }
 
 #[derive(Component)]
@@ -33,46 +191,336 @@ struct DebugColliderView {
 
 impl RectCollider {
     pub fn new(owner: Option<Entity>, offset: Vec2, width: f32, height: f32) -> Self {
+        RectCollider::with_shape(owner, offset, ColliderShape::rect(width, height))
+    }
+
+    pub fn with_shape(owner: Option<Entity>, offset: Vec2, shape: ColliderShape) -> Self {
         Self {
             owner,
             enabled: true,
             center: Vec2::ZERO,
+            previous_center: Vec2::ZERO,
             offset,
-            half_extends: Vec2::new(f32::abs(width) * 0.5, f32::abs(height) * 0.5),
+            rotation: 0.0,
+            shape,
+            layer: CollisionLayer::Terrain,
+            mask: CollisionMask::ALL,
         }
     }
 
+    pub fn with_layer(mut self, layer: CollisionLayer, mask: CollisionMask) -> Self {
+        self.layer = layer;
+        self.mask = mask;
+        self
+    }
+
+    pub fn layer(&self) -> CollisionLayer {
+        self.layer
+    }
+
+    pub fn interacts_with(&self, other: &RectCollider) -> bool {
+        self.enabled
+            && other.enabled
+            && self.mask.contains(other.layer)
+            && other.mask.contains(self.layer)
+    }
+
     pub fn disable(&mut self) {
         self.enabled = false;
     }
 
+    pub fn shape(&self) -> ColliderShape {
+        self.shape
+    }
+
     pub fn size(&self) -> Vec2 {
-        Vec2::new(self.half_extends.x * 2.0, self.half_extends.y * 2.0)
+        self.shape.size()
     }
 
     pub fn set_center(&mut self, value: Vec2) {
         self.center = value;
+        self.previous_center = value;
+    }
+
+    pub fn set_motion(&mut self, from: Vec2, to: Vec2) {
+        self.previous_center = from;
+        self.center = to;
+    }
+
+    pub fn moved(&self) -> bool {
+        self.previous_center != self.center
+    }
+
+    pub fn set_rotation(&mut self, value: f32) {
+        self.rotation = value;
+    }
+
+    pub fn position(&self) -> Vec2 {
+        self.center + self.offset
+    }
+
+    pub fn rotation(&self) -> f32 {
+        match self.shape {
+            ColliderShape::OrientedBox { .. } => self.rotation,
+            _ => 0.0,
+        }
     }
 
-    pub fn aabb_collides_with(&self, other: &RectCollider) -> bool {
-        if !self.enabled || !other.enabled {
+    pub fn bounds(&self) -> Vec2 {
+        match self.shape {
+            ColliderShape::OrientedBox { half_extends } => {
+                let (sin, cos) = self.rotation.sin_cos();
+                Vec2::new(
+                    cos.abs() * half_extends.x + sin.abs() * half_extends.y,
+                    sin.abs() * half_extends.x + cos.abs() * half_extends.y,
+                )
+            }
+            shape => shape.extends(),
+        }
+    }
+
+    pub fn collides_with(&self, other: &RectCollider) -> bool {
+        if !self.interacts_with(other) {
             return false;
         }
 
-        let self_pos = self.center + self.offset;
-        let self_xs = self_pos.x - self.half_extends.x;
-        let self_xe = self_pos.x + self.half_extends.x;
-        let self_ys = self_pos.y - self.half_extends.y;
-        let self_ye = self_pos.y + self.half_extends.y;
+        match (self.shape, other.shape) {
+            (
+                ColliderShape::Circle { radius },
+                ColliderShape::Circle {
+                    radius: other_radius,
+                },
+            ) => {
+                self.position().distance_squared(other.position())
+                    < (radius + other_radius) * (radius + other_radius)
+            }
+            (ColliderShape::Circle { radius }, _) => {
+                let closest = other.closest_point(self.position());
+                closest.distance_squared(self.position()) < radius * radius
+            }
+            (_, ColliderShape::Circle { radius }) => {
+                let closest = self.closest_point(other.position());
+                closest.distance_squared(other.position()) < radius * radius
+            }
+            _ => self.boxes_overlap(other),
+        }
+    }
 
-        let other_pos = other.center + other.offset;
-        let other_xs = other_pos.x - other.half_extends.x;
-        let other_xe = other_pos.x + other.half_extends.x;
-        let other_ys = other_pos.y - other.half_extends.y;
-        let other_ye = other_pos.y + other.half_extends.y;
+    pub fn closest_point(&self, point: Vec2) -> Vec2 {
+        let position = self.position();
+        match self.shape {
+            ColliderShape::Circle { radius } => {
+                let delta = point - position;
+                if delta.length_squared() <= radius * radius {
+                    point
+                } else {
+                    position + delta.normalize() * radius
+                }
+            }
+            ColliderShape::Aabb { half_extends } | ColliderShape::OrientedBox { half_extends } => {
+                let local = Vec2::from_angle(-self.rotation()).rotate(point - position);
+                let clamped = local.clamp(-half_extends, half_extends);
+                position + Vec2::from_angle(self.rotation()).rotate(clamped)
+            }
+        }
+    }
 
-        self_xs < other_xe && self_xe > other_xs && self_ys < other_ye && self_ye > other_ys
+    pub fn aabb_distance_to(&self, other: &RectCollider) -> f32 {
+        let delta = other.position() - self.position();
+        let gap = delta.abs() - (self.bounds() + other.bounds());
+        gap.max(Vec2::ZERO).length()
     }
+
+    pub fn sweep(&self, start: Vec2, end: Vec2, other: &RectCollider) -> Option<SweepHit> {
+        if !self.interacts_with(other) {
+            return None;
+        }
+
+        let motion = end - start;
+        let from = start + self.offset - other.position();
+        let (time, normal) = match (self.shape, other.shape) {
+            (
+                ColliderShape::Circle { radius },
+                ColliderShape::Circle {
+                    radius: other_radius,
+                },
+            ) => sweep_circle(from, motion, radius + other_radius)?,
+            (ColliderShape::Circle { radius }, _) => other.sweep_rounded(from, motion, radius)?,
+            (_, ColliderShape::Circle { radius }) => {
+                let (time, normal) = self.sweep_rounded(-from, -motion, radius)?;
+                (time, -normal)
+            }
+            _ => self.sweep_box(from, motion, other)?,
+        };
+
+        let position = start + motion * time;
+        Some(SweepHit {
+            time,
+            position,
+            point: other.closest_point(position + self.offset),
+            normal,
+        })
+    }
+
+    pub fn detect(&self, other: &RectCollider) -> Option<SweepHit> {
+        let other_motion = other.center - other.previous_center;
+        self.sweep(self.previous_center - other_motion, self.center, other)
+    }
+
+    pub fn sweep_first<'a, T>(
+        &self,
+        start: Vec2,
+        end: Vec2,
+        others: impl IntoIterator<Item = (T, &'a RectCollider)>,
+    ) -> Option<(T, SweepHit)> {
+        others
+            .into_iter()
+            .filter_map(|(item, other)| self.sweep(start, end, other).map(|hit| (item, hit)))
+            .min_by(|(_, a), (_, b)| a.time.total_cmp(&b.time))
+    }
+
+    fn swept_bounds(&self) -> (Vec2, Vec2) {
+        let start = self.previous_center + self.offset;
+        let end = self.position();
+        let bounds = self.bounds();
+        (start.min(end) - bounds, start.max(end) + bounds)
+    }
+
+    fn boxes_overlap(&self, other: &RectCollider) -> bool {
+        let delta = other.position() - self.position();
+        let self_axes = box_axes(self.rotation());
+        let other_axes = box_axes(other.rotation());
+        let self_extends = self.shape.extends();
+        let other_extends = other.shape.extends();
+
+        self_axes.iter().chain(other_axes.iter()).all(|axis| {
+            let self_radius = self_extends.x * self_axes[0].dot(*axis).abs()
+                + self_extends.y * self_axes[1].dot(*axis).abs();
+            let other_radius = other_extends.x * other_axes[0].dot(*axis).abs()
+                + other_extends.y * other_axes[1].dot(*axis).abs();
+            delta.dot(*axis).abs() < self_radius + other_radius
+        })
+    }
+
+    fn sweep_box(&self, from: Vec2, motion: Vec2, other: &RectCollider) -> Option<(f32, Vec2)> {
+        let self_axes = box_axes(self.rotation());
+        let other_axes = box_axes(other.rotation());
+        let self_extends = self.shape.extends();
+        let other_extends = other.shape.extends();
+
+        let slabs = self_axes.iter().chain(other_axes.iter()).map(|axis| {
+            let radius = self_extends.x * self_axes[0].dot(*axis).abs()
+                + self_extends.y * self_axes[1].dot(*axis).abs()
+                + other_extends.x * other_axes[0].dot(*axis).abs()
+                + other_extends.y * other_axes[1].dot(*axis).abs();
+            (*axis, radius)
+        });
+        sweep_slabs(from, motion, slabs)
+    }
+
+    fn sweep_rounded(&self, from: Vec2, motion: Vec2, radius: f32) -> Option<(f32, Vec2)> {
+        let to_local = Vec2::from_angle(-self.rotation());
+        let from = to_local.rotate(from);
+        let motion = to_local.rotate(motion);
+        let extends = self.shape.extends();
+
+        let sides = [
+            Vec2::new(extends.x + radius, extends.y),
+            Vec2::new(extends.x, extends.y + radius),
+        ]
+        .into_iter()
+        .filter_map(|side| sweep_slabs(from, motion, [(Vec2::X, side.x), (Vec2::Y, side.y)]));
+        let corners = [
+            Vec2::new(-extends.x, -extends.y),
+            Vec2::new(extends.x, -extends.y),
+            Vec2::new(-extends.x, extends.y),
+            Vec2::new(extends.x, extends.y),
+        ]
+        .into_iter()
+        .filter_map(|corner| sweep_circle(from - corner, motion, radius));
+
+        sides
+            .chain(corners)
+            .min_by(|(a, _), (b, _)| a.total_cmp(b))
+            .map(|(time, normal)| (time, Vec2::from_angle(self.rotation()).rotate(normal)))
+    }
+}
+
+fn pair_key(entity: Entity, other: Entity) -> (Entity, Entity) {
+    if entity < other {
+        (entity, other)
+    } else {
+        (other, entity)
+    }
+}
+
+fn box_axes(rotation: f32) -> [Vec2; 2] {
+    let axis = Vec2::from_angle(rotation);
+    [axis, axis.perp()]
+}
+
+fn sweep_slabs(
+    from: Vec2,
+    motion: Vec2,
+    slabs: impl IntoIterator<Item = (Vec2, f32)>,
+) -> Option<(f32, Vec2)> {
+    let mut enter_time = f32::NEG_INFINITY;
+    let mut exit_time = f32::INFINITY;
+    let mut normal = Vec2::ZERO;
+    for (axis, radius) in slabs {
+        let distance = from.dot(axis);
+        let speed = motion.dot(axis);
+        if speed.abs() < f32::EPSILON {
+            if distance.abs() >= radius {
+                return None;
+            }
+            continue;
+        }
+
+        let first = (-radius - distance) / speed;
+        let second = (radius - distance) / speed;
+        let axis_enter = first.min(second);
+        if axis_enter > enter_time {
+            enter_time = axis_enter;
+            normal = -axis * speed.signum();
+        }
+
+        exit_time = exit_time.min(first.max(second));
+        if enter_time >= exit_time {
+            return None;
+        }
+    }
+
+    if enter_time > 1.0 || exit_time <= 0.0 {
+        return None;
+    }
+
+    Some((enter_time.max(0.0), normal))
+}
+
+fn sweep_circle(from: Vec2, motion: Vec2, radius: f32) -> Option<(f32, Vec2)> {
+    let gap = from.length_squared() - radius * radius;
+    if gap < 0.0 {
+        return Some((0.0, from.normalize_or_zero()));
+    }
+
+    let speed = motion.length_squared();
+    if speed <= 0.0 {
+        return None;
+    }
+
+    let approach = from.dot(motion);
+    let discriminant = approach * approach - speed * gap;
+    if discriminant < 0.0 {
+        return None;
+    }
+
+    let time = (-approach - discriminant.sqrt()) / speed;
+    if !(0.0..=1.0).contains(&time) {
+        return None;
+    }
+
+    Some((time, (from + motion * time) / radius))
 }
 
 fn collider_debug_switch_system(
@@ -84,24 +532,30 @@ fn collider_debug_switch_system(
     }
 }
 
+fn setup_collider_debug_meshes(mut commands: Commands, mut meshes: ResMut<Assets<Mesh>>) {
+    commands.insert_resource(ColliderDebugMeshes {
+        quad: meshes.add(Mesh::from(shape::Quad::new(Vec2::ONE))),
+        circle: meshes.add(Mesh::from(shape::Circle::new(0.5))),
+    });
+}
+
 fn collider_added_debug_system(
     mut commands: Commands,
+    debug_meshes: Res<ColliderDebugMeshes>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
     colliders_added: Query<(Entity, &RectCollider), Added<RectCollider>>,
 ) {
-    for (entity, collidder) in colliders_added.iter() {
+    for (entity, collider) in colliders_added.iter() {
+        let mesh = match collider.shape() {
+            ColliderShape::Circle { .. } => debug_meshes.circle.clone(),
+            _ => debug_meshes.quad.clone(),
+        };
+
         commands
-            .spawn(SpriteBundle {
+            .spawn(MaterialMesh2dBundle {
+                mesh: Mesh2dHandle(mesh),
+                material: materials.add(ColorMaterial::from(DEBUG_COLOR)),
                 visibility: Visibility::INVISIBLE,
-                sprite: Sprite {
-                    custom_size: collidder.size().into(),
-                    color: Color::Rgba {
-                        red: 0.0,
-                        green: 0.0,
-                        blue: 1.0,
-                        alpha: 0.5,
-                    },
-                    ..default()
-                },
                 ..default()
             })
             .insert(DebugColliderView { collider: entity });
@@ -111,50 +565,286 @@ fn collider_added_debug_system(
 fn collider_debug_update_system(
     mut commands: Commands,
     collider_settings: Res<ColliderSettings>,
-    colliders: Query<(Entity, &RectCollider)>,
+    collision_pairs: Res<CollisionPairs>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    colliders: Query<&RectCollider>,
     mut colliders_debug: Query<(
         Entity,
-        &mut Sprite,
+        &Handle<ColorMaterial>,
         &mut Transform,
         &mut Visibility,
         &DebugColliderView,
     )>,
 ) {
-    for (entity, mut sprite, mut transform, mut visibility, collider_debug) in
+    for (entity, material, mut transform, mut visibility, collider_debug) in
         colliders_debug.iter_mut()
     {
-        if let Ok((collider_entity, collider)) = colliders.get(collider_debug.collider) {
+        if let Ok(collider) = colliders.get(collider_debug.collider) {
             visibility.is_visible = collider_settings.show_debugs;
-            sprite.custom_size = collider.size().into();
-            let collider_pos = collider.center + collider.offset;
-            transform.translation = Vec3::new(collider_pos.x, collider_pos.y, 10.0);
-
-            let mut color = Color::Rgba {
-                red: 0.0,
-                green: 0.0,
-                blue: 1.0,
-                alpha: 0.5,
-            };
-            for (other_entity, other) in colliders.iter() {
-                if collider_entity == other_entity {
-                    continue;
-                }
+            transform.translation = collider.position().extend(10.0);
+            transform.rotation = Quat::from_rotation_z(collider.rotation());
+            transform.scale = collider.size().extend(1.0);
 
-                if collider.aabb_collides_with(other) {
-                    color.set_r(1.0);
-                    break;
-                }
+            let hit = collision_pairs.touching(collider_debug.collider);
+            if let Some(material) = materials.get_mut(material) {
+                material.color = if hit { DEBUG_HIT_COLOR } else { DEBUG_COLOR };
             }
-            sprite.color = color;
         } else {
+            materials.remove(material);
             commands.entity(entity).despawn_recursive();
         }
     }
 }
 
+fn collision_detection_system(
+    mut collision_pairs: ResMut<CollisionPairs>,
+    colliders: Query<(Entity, &RectCollider)>,
+    mut collisions_started: EventWriter<CollisionStarted>,
+    mut collisions_ended: EventWriter<CollisionEnded>,
+) {
+    let mut candidates: Vec<(Entity, &RectCollider, Vec2, Vec2)> = colliders
+        .iter()
+        .filter(|(_, collider)| collider.enabled)
+        .map(|(entity, collider)| {
+            let (min, max) = collider.swept_bounds();
+            (entity, collider, min, max)
+        })
+        .collect();
+    candidates.sort_by(|a, b| a.2.x.total_cmp(&b.2.x));
+
+    let mut active = HashMap::new();
+    for (index, &(entity, collider, min, max)) in candidates.iter().enumerate() {
+        for &(other, other_collider, other_min, other_max) in &candidates[index + 1..] {
+            if other_min.x >= max.x {
+                break;
+            }
+            if other_min.y >= max.y || other_max.y <= min.y {
+                continue;
+            }
+
+            let ((mover, mover_collider), (target, target_collider)) =
+                if collider.moved() || !other_collider.moved() {
+                    ((entity, collider), (other, other_collider))
+                } else {
+                    ((other, other_collider), (entity, collider))
+                };
+            let Some(hit) = mover_collider.detect(target_collider) else {
+                continue;
+            };
+
+            let pair = CollisionPair {
+                entity: mover,
+                owner: mover_collider.owner,
+                other: target,
+                other_owner: target_collider.owner,
+            };
+            let key = pair_key(entity, other);
+            if !collision_pairs.active.contains_key(&key) {
+                collisions_started.send(CollisionStarted { pair, hit });
+            }
+            active.insert(key, pair);
+        }
+    }
+
+    for (key, pair) in collision_pairs.active.iter() {
+        if !active.contains_key(key) {
+            collisions_ended.send(CollisionEnded { pair: *pair });
+        }
+    }
+    collision_pairs.active = active;
+}
+
 fn colliders_position_update_system(mut colliders: Query<(&mut RectCollider, &GlobalTransform)>) {
     for (mut collider, transform) in colliders.iter_mut() {
-        let new_pos = transform.translation().truncate();
-        collider.center = new_pos;
+        let (_, rotation, translation) = transform.to_scale_rotation_translation();
+        collider.set_center(translation.truncate());
+        collider.rotation = rotation.to_euler(EulerRot::ZYX).0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::FRAC_PI_4;
+
+    use super::super::geometry::{self, Hitbox};
+    use super::*;
+
+    fn collider(shape: ColliderShape, center: Vec2, rotation: f32) -> RectCollider {
+        let mut collider = RectCollider::with_shape(None, Vec2::ZERO, shape);
+        collider.set_center(center);
+        collider.set_rotation(rotation);
+        collider
+    }
+
+    fn arrow() -> RectCollider {
+        collider(ColliderShape::oriented(0.3, 0.3), Vec2::ZERO, 0.0)
+    }
+
+    #[test]
+    fn fast_arrow_hits_thin_box() {
+        let wall = collider(ColliderShape::rect(0.1, 1.0), Vec2::ZERO, 0.0);
+        let hit = arrow()
+            .sweep(Vec2::new(-100.0, 0.0), Vec2::new(100.0, 0.0), &wall)
+            .expect("arrow passed through the wall");
+
+        assert!((hit.time - (100.0 - 0.2) / 200.0).abs() < 1e-4);
+        assert!((hit.position.x + 0.2).abs() < 1e-2);
+        assert_eq!(hit.normal, Vec2::NEG_X);
+    }
+
+    #[test]
+    fn fast_arrow_clips_box_corner() {
+        let target = collider(ColliderShape::rect(0.8, 1.0), Vec2::ZERO, 0.0);
+        let clipping = Vec2::new(-50.0, 51.195);
+        let passing = Vec2::new(-50.0, 51.205);
+        let motion = Vec2::new(100.0, -100.0);
+
+        assert!(arrow()
+            .sweep(clipping, clipping + motion, &target)
+            .is_some());
+        assert!(arrow().sweep(passing, passing + motion, &target).is_none());
+    }
+
+    #[test]
+    fn fast_arrow_grazes_circle() {
+        let head = collider(ColliderShape::circle(0.35), Vec2::ZERO, 0.0);
+        let grazing = Vec2::new(-100.0, 0.495);
+        let passing = Vec2::new(-100.0, 0.505);
+        let motion = Vec2::new(200.0, 0.0);
+
+        let hit = arrow()
+            .sweep(grazing, grazing + motion, &head)
+            .expect("arrow passed through the head");
+        assert!(hit.normal.y > 0.0);
+        assert!(arrow().sweep(passing, passing + motion, &head).is_none());
+    }
+
+    #[test]
+    fn fast_circle_hits_circle() {
+        let ball = collider(ColliderShape::circle(0.1), Vec2::ZERO, 0.0);
+        let head = collider(ColliderShape::circle(0.35), Vec2::ZERO, 0.0);
+        let hit = ball
+            .sweep(Vec2::new(-10.0, 0.0), Vec2::new(10.0, 0.0), &head)
+            .expect("circle passed through the head");
+
+        assert!((hit.time - (10.0 - 0.45) / 20.0).abs() < 1e-4);
+        assert!((hit.normal - Vec2::NEG_X).length() < 1e-4);
+    }
+
+    #[test]
+    fn rotated_box_is_separated_along_its_own_axes() {
+        let stick = collider(ColliderShape::oriented(2.0, 0.2), Vec2::ZERO, FRAC_PI_4);
+        let beside = collider(ColliderShape::rect(0.4, 0.4), Vec2::new(0.7, -0.7), 0.0);
+        let across = collider(ColliderShape::rect(0.4, 0.4), Vec2::new(0.5, 0.5), 0.0);
+
+        assert!(stick.aabb_distance_to(&beside) == 0.0);
+        assert!(!stick.collides_with(&beside));
+        assert!(!beside.collides_with(&stick));
+        assert!(stick.collides_with(&across));
+    }
+
+    #[test]
+    fn aabb_ignores_rotation() {
+        let mut aabb = collider(ColliderShape::rect(2.0, 0.2), Vec2::ZERO, FRAC_PI_4);
+        aabb.set_rotation(FRAC_PI_4);
+        let above = collider(ColliderShape::rect(0.2, 0.2), Vec2::new(0.0, 0.5), 0.0);
+
+        assert_eq!(aabb.rotation(), 0.0);
+        assert!(!aabb.collides_with(&above));
+    }
+
+    #[test]
+    fn circle_misses_box_corner() {
+        let head = collider(ColliderShape::circle(0.35), Vec2::ZERO, 0.0);
+        let corner = collider(ColliderShape::rect(0.2, 0.2), Vec2::new(0.4, 0.4), 0.0);
+        let side = collider(ColliderShape::rect(0.2, 0.2), Vec2::new(0.4, 0.0), 0.0);
+
+        assert!(head.aabb_distance_to(&corner) == 0.0);
+        assert!(!head.collides_with(&corner));
+        assert!(!corner.collides_with(&head));
+        assert!(head.collides_with(&side));
+        assert!(side.collides_with(&head));
+    }
+
+    #[test]
+    fn circle_touches_rotated_box() {
+        let head = collider(ColliderShape::circle(0.35), Vec2::ZERO, 0.0);
+        let stick = collider(
+            ColliderShape::oriented(2.0, 0.2),
+            Vec2::new(0.4, 0.4),
+            FRAC_PI_4,
+        );
+        let parallel = collider(
+            ColliderShape::oriented(2.0, 0.2),
+            Vec2::new(0.4, -0.4),
+            FRAC_PI_4,
+        );
+
+        assert!(head.collides_with(&stick));
+        assert!(!head.collides_with(&parallel));
+    }
+
+    #[test]
+    fn circles_collide_within_summed_radii() {
+        let head = collider(ColliderShape::circle(0.35), Vec2::ZERO, 0.0);
+        let near = collider(ColliderShape::circle(0.1), Vec2::new(0.3, 0.3), 0.0);
+        let far = collider(ColliderShape::circle(0.1), Vec2::new(0.35, 0.35), 0.0);
+
+        assert!(head.collides_with(&near));
+        assert!(!head.collides_with(&far));
+    }
+
+    #[test]
+    fn arrows_only_touch_the_other_archer() {
+        let arrow = geometry::arrow_collider(None, false);
+        let own_head = Hitbox::HEAD.collider(None, false);
+        let enemy_head = Hitbox::HEAD.collider(None, true);
+        let enemy_arrow = geometry::arrow_collider(None, true);
+
+        assert!(arrow.collides_with(&enemy_head));
+        assert!(enemy_head.collides_with(&arrow));
+        assert!(!arrow.collides_with(&own_head));
+        assert!(!own_head.collides_with(&arrow));
+        assert!(enemy_arrow.collides_with(&own_head));
+        assert!(!arrow.collides_with(&enemy_arrow));
+        assert!(arrow.detect(&enemy_head).is_some());
+        assert!(arrow.detect(&own_head).is_none());
+    }
+
+    #[test]
+    fn masks_must_match_both_ways() {
+        let shape = ColliderShape::rect(1.0, 1.0);
+        let button = RectCollider::with_shape(None, Vec2::ZERO, shape).with_layer(
+            CollisionLayer::MenuButton,
+            CollisionMask::NONE.with(CollisionLayer::Arrow),
+        );
+        let terrain = RectCollider::with_shape(None, Vec2::ZERO, shape)
+            .with_layer(CollisionLayer::Terrain, CollisionMask::ALL);
+        let mut arrow = geometry::arrow_collider(None, false);
+
+        assert!(arrow.interacts_with(&button));
+        assert!(arrow.interacts_with(&terrain));
+        assert!(!terrain.interacts_with(&button));
+        arrow.disable();
+        assert!(!arrow.interacts_with(&button));
+    }
+
+    #[test]
+    fn sweep_starting_inside_hits_immediately() {
+        let target = collider(ColliderShape::rect(1.0, 1.0), Vec2::ZERO, 0.0);
+        let hit = arrow()
+            .sweep(Vec2::ZERO, Vec2::new(5.0, 0.0), &target)
+            .expect("overlapping arrow was not reported");
+
+        assert_eq!(hit.time, 0.0);
+    }
+
+    #[test]
+    fn sweep_ignores_targets_behind_the_arrow() {
+        let target = collider(ColliderShape::rect(1.0, 1.0), Vec2::ZERO, 0.0);
+
+        assert!(arrow()
+            .sweep(Vec2::new(2.0, 0.0), Vec2::new(50.0, 0.0), &target)
+            .is_none());
     }
 }
@@ -0,0 +1,322 @@
+use bevy::prelude::*;
+
+use super::{
+    ai_controls::AIControls,
+    ai_model::{AIModel, ModelLocation},
+    GameStage, GameState, GameTurn,
+};
+
+const ADAPTIVE_MAX_STEPS: i32 = 3;
+const ADAPTIVE_NOISE_STEP: f32 = 0.02;
+const ADAPTIVE_PRESSURE_THRESHOLD: i32 = 2;
+const ADAPTIVE_RECENT_SHOTS: usize = 4;
+
+pub struct DifficultyPlugin;
+
+impl Plugin for DifficultyPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Difficulty>()
+            .init_resource::<AdaptiveDifficulty>()
+            .add_system(difficulty_keyboard_update_system)
+            .add_system(adaptive_difficulty_update_system)
+            .add_system(difficulty_model_update_system);
+    }
+}
+
+#[derive(Resource, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+    Expert,
+}
+
+impl Difficulty {
+    pub const ALL: [Difficulty; 4] = [
+        Difficulty::Easy,
+        Difficulty::Normal,
+        Difficulty::Hard,
+        Difficulty::Expert,
+    ];
+
+    pub fn aim_noise(&self) -> f32 {
+        match self {
+            Difficulty::Easy => 0.06,
+            Difficulty::Normal => 0.02,
+            Difficulty::Hard => 0.01,
+            Difficulty::Expert => 0.0,
+        }
+    }
+
+    pub fn wind_up_time(&self) -> f32 {
+        match self {
+            Difficulty::Easy => 1.5,
+            Difficulty::Normal => 1.0,
+            Difficulty::Hard => 0.8,
+            Difficulty::Expert => 0.6,
+        }
+    }
+
+    pub fn model_asset(&self) -> &'static str {
+        match self {
+            Difficulty::Easy => "ai/net_easy.model.json",
+            Difficulty::Normal => "ai/net_normal.model.json",
+            Difficulty::Hard => "ai/net_hard.model.json",
+            Difficulty::Expert => "ai/net_expert.model.json",
+        }
+    }
+
+    pub fn shifted(&self, offset: i32) -> Difficulty {
+        let index = Difficulty::ALL
+            .iter()
+            .position(|level| level == self)
+            .unwrap_or_default() as i32;
+        let shifted = (index + offset).clamp(0, Difficulty::ALL.len() as i32 - 1);
+        Difficulty::ALL[shifted as usize]
+    }
+
+    pub fn color(&self) -> Color {
+        match self {
+            Difficulty::Easy => Color::rgb(0.3, 0.8, 0.3),
+            Difficulty::Normal => Color::rgb(0.9, 0.8, 0.2),
+            Difficulty::Hard => Color::rgb(0.9, 0.5, 0.1),
+            Difficulty::Expert => Color::rgb(0.8, 0.1, 0.1),
+        }
+    }
+}
+
+#[derive(Resource)]
+pub struct AdaptiveDifficulty {
+    pub enabled: bool,
+    steps: i32,
+    shots_seen: usize,
+    history: Vec<i32>,
+}
+
+impl Default for AdaptiveDifficulty {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            steps: 0,
+            shots_seen: 0,
+            history: Vec::new(),
+        }
+    }
+}
+
+impl AdaptiveDifficulty {
+    pub fn reset(&mut self) {
+        self.steps = 0;
+        self.shots_seen = 0;
+        self.history.clear();
+    }
+
+    pub fn steps(&self) -> i32 {
+        self.steps
+    }
+
+    pub fn history(&self) -> &[i32] {
+        &self.history
+    }
+
+    pub fn aim_noise(&self, difficulty: Difficulty) -> f32 {
+        (difficulty.aim_noise() - self.steps as f32 * ADAPTIVE_NOISE_STEP).max(0.0)
+    }
+
+    pub fn model_level(&self, difficulty: Difficulty) -> Difficulty {
+        if self.steps >= ADAPTIVE_MAX_STEPS {
+            difficulty.shifted(1)
+        } else if self.steps <= -ADAPTIVE_MAX_STEPS {
+            difficulty.shifted(-1)
+        } else {
+            difficulty
+        }
+    }
+
+    pub fn step_color(difficulty: Difficulty, steps: i32) -> Color {
+        match steps.signum() {
+            1 => Difficulty::Expert.color(),
+            -1 => Difficulty::Easy.color(),
+            _ => difficulty.color(),
+        }
+    }
+
+    pub fn step_height(steps: i32) -> f32 {
+        0.1 + 0.1 * (steps + ADAPTIVE_MAX_STEPS) as f32
+    }
+
+    pub fn adjust(&mut self, game_state: &GameState) {
+        let lead = game_state.player_health - game_state.enemy_health;
+        let form: i32 = game_state
+            .shot_history
+            .iter()
+            .rev()
+            .take(ADAPTIVE_RECENT_SHOTS)
+            .filter(|shot| shot.hit())
+            .map(|shot| match shot.shooter {
+                GameTurn::Player => 1,
+                GameTurn::Enemy => -1,
+            })
+            .sum();
+        let pressure = lead + form;
+
+        let step = if pressure >= ADAPTIVE_PRESSURE_THRESHOLD {
+            1
+        } else if pressure <= -ADAPTIVE_PRESSURE_THRESHOLD {
+            -1
+        } else {
+            -self.steps.signum()
+        };
+        self.steps = (self.steps + step).clamp(-ADAPTIVE_MAX_STEPS, ADAPTIVE_MAX_STEPS);
+        self.history.push(self.steps);
+    }
+}
+
+fn difficulty_keyboard_update_system(
+    keyboard: Res<Input<KeyCode>>,
+    game_state: Res<GameState>,
+    mut difficulty: ResMut<Difficulty>,
+    mut adaptive_difficulty: ResMut<AdaptiveDifficulty>,
+) {
+    if game_state.stage != GameStage::Menu {
+        return;
+    }
+
+    if keyboard.just_pressed(KeyCode::A) {
+        adaptive_difficulty.enabled = !adaptive_difficulty.enabled;
+        info!(
+            "Adaptive difficulty {}",
+            if adaptive_difficulty.enabled {
+                "enabled"
+            } else {
+                "disabled"
+            }
+        );
+    }
+
+    let keys = [KeyCode::Key1, KeyCode::Key2, KeyCode::Key3, KeyCode::Key4];
+    for (key, level) in keys.iter().zip(Difficulty::ALL) {
+        if keyboard.just_pressed(*key) && *difficulty != level {
+            *difficulty = level;
+        }
+    }
+}
+
+fn adaptive_difficulty_update_system(
+    game_state: Res<GameState>,
+    difficulty: Res<Difficulty>,
+    mut adaptive_difficulty: ResMut<AdaptiveDifficulty>,
+) {
+    if game_state.stage == GameStage::Menu {
+        if adaptive_difficulty.shots_seen > 0 {
+            adaptive_difficulty.reset();
+        }
+        return;
+    }
+
+    if !adaptive_difficulty.enabled
+        || adaptive_difficulty.shots_seen >= game_state.shot_history.len()
+    {
+        return;
+    }
+
+    adaptive_difficulty.shots_seen = game_state.shot_history.len();
+    let previous_steps = adaptive_difficulty.steps();
+    adaptive_difficulty.adjust(&game_state);
+
+    if adaptive_difficulty.steps() != previous_steps {
+        info!(
+            "Adaptive difficulty step {} (aim noise {:.3}, model {:?})",
+            adaptive_difficulty.steps(),
+            adaptive_difficulty.aim_noise(*difficulty),
+            adaptive_difficulty.model_level(*difficulty)
+        );
+    }
+}
+
+fn difficulty_model_update_system(
+    asset_server: Res<AssetServer>,
+    difficulty: Res<Difficulty>,
+    adaptive_difficulty: Res<AdaptiveDifficulty>,
+    mut ai_controls: ResMut<AIControls>,
+    mut active_level: Local<Option<Difficulty>>,
+) {
+    let level = adaptive_difficulty.model_level(*difficulty);
+    if !difficulty.is_changed() && *active_level == Some(level) {
+        return;
+    }
+    *active_level = Some(level);
+
+    let model_asset = level.model_asset();
+    let model: Handle<AIModel> = if ModelLocation::asset_root().join(model_asset).is_file() {
+        asset_server.load(model_asset)
+    } else {
+        ai_controls.base_model()
+    };
+    ai_controls.use_model(model);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{neural_network::HitZone, ShotRecord};
+
+    fn game_state(player_health: i32, enemy_health: i32, hits: &[GameTurn]) -> GameState {
+        let mut game_state = GameState::new();
+        game_state.player_health = player_health;
+        game_state.enemy_health = enemy_health;
+        game_state.shot_history = hits
+            .iter()
+            .map(|&shooter| ShotRecord {
+                shooter,
+                damage: HitZone::Body.damage(),
+                offset: 0.0,
+                zone: HitZone::Body,
+            })
+            .collect();
+        game_state
+    }
+
+    #[test]
+    fn player_lead_raises_the_step() {
+        let mut adaptive_difficulty = AdaptiveDifficulty::default();
+        adaptive_difficulty.adjust(&game_state(10, 7, &[]));
+        assert_eq!(adaptive_difficulty.steps(), 1);
+
+        let mut adaptive_difficulty = AdaptiveDifficulty::default();
+        adaptive_difficulty.adjust(&game_state(10, 10, &[GameTurn::Player, GameTurn::Player]));
+        assert_eq!(adaptive_difficulty.steps(), 1);
+    }
+
+    #[test]
+    fn steps_are_clamped() {
+        let mut adaptive_difficulty = AdaptiveDifficulty::default();
+        for _ in 0..10 {
+            adaptive_difficulty.adjust(&game_state(10, 2, &[]));
+        }
+        assert_eq!(adaptive_difficulty.steps(), ADAPTIVE_MAX_STEPS);
+
+        for _ in 0..20 {
+            adaptive_difficulty.adjust(&game_state(2, 10, &[]));
+        }
+        assert_eq!(adaptive_difficulty.steps(), -ADAPTIVE_MAX_STEPS);
+        assert_eq!(adaptive_difficulty.history().len(), 30);
+    }
+
+    #[test]
+    fn even_match_decays_toward_zero() {
+        let mut adaptive_difficulty = AdaptiveDifficulty::default();
+        for _ in 0..ADAPTIVE_MAX_STEPS {
+            adaptive_difficulty.adjust(&game_state(2, 10, &[]));
+        }
+
+        let even = game_state(6, 6, &[GameTurn::Player, GameTurn::Enemy]);
+        for expected in (-ADAPTIVE_MAX_STEPS + 1)..=0 {
+            adaptive_difficulty.adjust(&even);
+            assert_eq!(adaptive_difficulty.steps(), expected);
+        }
+        adaptive_difficulty.adjust(&even);
+        assert_eq!(adaptive_difficulty.steps(), 0);
+    }
+}
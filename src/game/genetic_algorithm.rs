@@ -0,0 +1,508 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::neural_network::{
+    HitCounts, MutationNoise, MutationSettings, NetworkTopology, NeuralNetwork,
+};
+
+#[derive(Serialize, Deserialize, Clone)]
+struct Agent {
+    net: NeuralNetwork,
+    score: i32,
+    fitness: f32,
+    #[serde(default)]
+    hits: HitCounts,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SelectionMethod {
+    Roulette,
+    Tournament(usize),
+    Rank,
+}
+
+impl FromStr for SelectionMethod {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let mut parts = value.splitn(2, ':');
+        match (parts.next(), parts.next()) {
+            (Some("roulette"), None) => Ok(SelectionMethod::Roulette),
+            (Some("rank"), None) => Ok(SelectionMethod::Rank),
+            (Some("tournament"), None) => Ok(SelectionMethod::Tournament(3)),
+            (Some("tournament"), Some(size)) => match size.parse() {
+                Ok(size) if size > 0 => Ok(SelectionMethod::Tournament(size)),
+                _ => Err(format!("Invalid tournament size '{size}'")),
+            },
+            _ => Err(format!("Unknown selection method '{value}'")),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct GeneticAlgorithmSettings {
+    pub pop_size: usize,
+    pub topology: NetworkTopology,
+    pub weight_mutation: MutationSettings,
+    pub bias_mutation: MutationSettings,
+    pub seed: u64,
+    pub elitism: usize,
+    pub selection: SelectionMethod,
+    #[serde(default)]
+    pub shaping: f32,
+}
+
+impl Default for GeneticAlgorithmSettings {
+    fn default() -> Self {
+        Self {
+            pop_size: 1024,
+            topology: NetworkTopology::default(),
+            weight_mutation: MutationSettings::new(1.0, 0.5, MutationNoise::Uniform),
+            bias_mutation: MutationSettings::new(1.0, 0.5, MutationNoise::Uniform),
+            seed: 0,
+            elitism: 2,
+            selection: SelectionMethod::Roulette,
+            shaping: 0.0,
+        }
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct GenerationStats {
+    pub generation: u32,
+    pub best_score: i32,
+    pub mean_score: f32,
+    pub median_score: f32,
+    pub worst_score: i32,
+    pub diversity: f32,
+    pub best_hits: HitCounts,
+    pub population_hits: HitCounts,
+}
+
+struct CheckpointSettings {
+    path: PathBuf,
+    interval: u32,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct GeneticAlgorithm {
+    settings: GeneticAlgorithmSettings,
+    current_gen: u32,
+    agents: Vec<Agent>,
+    random: ChaCha8Rng,
+    #[serde(skip)]
+    checkpoints: Option<CheckpointSettings>,
+}
+
+impl GeneticAlgorithm {
+    pub fn new(settings: GeneticAlgorithmSettings) -> Self {
+        let random = ChaCha8Rng::seed_from_u64(settings.seed);
+        Self {
+            settings,
+            current_gen: 0,
+            agents: Vec::new(),
+            random,
+            checkpoints: None,
+        }
+    }
+
+    pub fn resume<P: AsRef<Path>>(path: P) -> Result<Self, String> {
+        let path = path.as_ref();
+        let serialized = fs::read_to_string(path)
+            .map_err(|error| format!("Failed to read '{}': {error}", path.display()))?;
+        serde_json::from_str(&serialized)
+            .map_err(|error| format!("Failed to deserialize '{}': {error}", path.display()))
+    }
+
+    pub fn with_checkpoints<P: Into<PathBuf>>(mut self, path: P, interval: u32) -> Self {
+        self.checkpoints = Some(CheckpointSettings {
+            path: path.into(),
+            interval: interval.max(1),
+        });
+        self
+    }
+
+    pub fn settings(&self) -> &GeneticAlgorithmSettings {
+        &self.settings
+    }
+
+    pub fn current_generation(&self) -> u32 {
+        self.current_gen
+    }
+
+    pub fn save_checkpoint(&self, path: &Path) -> Result<(), String> {
+        let serialized = serde_json::to_string(self)
+            .map_err(|error| format!("Failed to serialize checkpoint: {error}"))?;
+        let temp_path = path.with_extension("tmp");
+        fs::write(&temp_path, serialized)
+            .and_then(|_| fs::rename(&temp_path, path))
+            .map_err(|error| format!("Failed to write '{}': {error}", path.display()))
+    }
+
+    pub fn get_best(&mut self, generations: u32) -> Result<NeuralNetwork, String> {
+        self.get_best_with_progress(generations, |_| {})
+    }
+
+    pub fn get_best_with_progress<F: FnMut(&GenerationStats)>(
+        &mut self,
+        generations: u32,
+        mut on_generation: F,
+    ) -> Result<NeuralNetwork, String> {
+        if self.agents.is_empty() {
+            self.current_gen = 0;
+            self.init_random();
+        }
+
+        while self.current_gen < generations {
+            self.calculate_fitness();
+            on_generation(&self.stats());
+
+            if let Some(checkpoints) = &self.checkpoints {
+                if (self.current_gen + 1) % checkpoints.interval == 0 {
+                    self.save_checkpoint(&checkpoints.path)?;
+                }
+            }
+
+            self.new_generation();
+
+            self.current_gen += 1;
+        }
+
+        self.calculate_fitness();
+        on_generation(&self.stats());
+
+        Ok(self.best_agent().net.clone())
+    }
+
+    fn best_agent(&self) -> &Agent {
+        let mut best = &self.agents[0];
+        for agent in self.agents.iter() {
+            if agent.score > best.score {
+                best = agent;
+            }
+        }
+
+        best
+    }
+
+    fn stats(&self) -> GenerationStats {
+        let mut scores: Vec<i32> = self.agents.iter().map(|agent| agent.score).collect();
+        scores.sort_unstable();
+
+        let score_sum: i64 = scores.iter().map(|&score| score as i64).sum();
+        let mean_score = score_sum as f32 / scores.len() as f32;
+        let middle = scores.len() / 2;
+        let median_score = if scores.len() % 2 == 0 {
+            (scores[middle - 1] + scores[middle]) as f32 * 0.5
+        } else {
+            scores[middle] as f32
+        };
+
+        let mut population_hits = HitCounts::default();
+        for agent in self.agents.iter() {
+            population_hits.merge(&agent.hits);
+        }
+
+        let best = self.best_agent();
+        GenerationStats {
+            generation: self.current_gen,
+            best_score: best.score,
+            mean_score,
+            median_score,
+            worst_score: scores[0],
+            diversity: self.diversity(),
+            best_hits: best.hits,
+            population_hits,
+        }
+    }
+
+    fn diversity(&self) -> f32 {
+        let genes: Vec<Vec<f32>> = self
+            .agents
+            .iter()
+            .map(|agent| agent.net.genes().collect())
+            .collect();
+        let genes_count = genes[0].len();
+        if genes_count == 0 {
+            return 0.0;
+        }
+
+        let agents_count = genes.len() as f32;
+        let mut deviation_sum = 0.0;
+        for n_gene in 0..genes_count {
+            let mean = genes.iter().map(|agent| agent[n_gene]).sum::<f32>() / agents_count;
+            let variance = genes
+                .iter()
+                .map(|agent| (agent[n_gene] - mean).powi(2))
+                .sum::<f32>()
+                / agents_count;
+            deviation_sum += variance.sqrt();
+        }
+
+        deviation_sum / genes_count as f32
+    }
+
+    fn init_random(&mut self) {
+        let mut random_agents: Vec<Agent> = Vec::with_capacity(self.settings.pop_size);
+        for _ in 0..self.settings.pop_size {
+            random_agents.push(Agent {
+                net: NeuralNetwork::new(&self.settings.topology, &mut self.random),
+                score: 0,
+                fitness: 0.0,
+                hits: HitCounts::default(),
+            });
+        }
+
+        self.agents = random_agents;
+    }
+
+    fn calculate_fitness(&mut self) {
+        let shaping = self.settings.shaping;
+        self.agents.par_iter_mut().for_each(|agent| {
+            (agent.score, agent.hits) = agent.net.shaped_score_with_hits(shaping)
+        });
+
+        let score_sum: i64 = self
+            .agents
+            .iter()
+            .map(|agent| agent.score.max(0) as i64)
+            .sum();
+
+        let agents_count = self.agents.len() as f32;
+        for agent in self.agents.iter_mut() {
+            agent.fitness = if score_sum > 0 {
+                agent.score.max(0) as f32 / score_sum as f32
+            } else {
+                1.0 / agents_count
+            };
+        }
+    }
+
+    fn selection_weights(&self) -> Vec<f32> {
+        match self.settings.selection {
+            SelectionMethod::Roulette => self.agents.iter().map(|agent| agent.fitness).collect(),
+            SelectionMethod::Tournament(_) => Vec::new(),
+            SelectionMethod::Rank => {
+                let mut ranked: Vec<usize> = (0..self.agents.len()).collect();
+                ranked.sort_by_key(|&index| self.agents[index].score);
+                let mut weights = vec![0.0; self.agents.len()];
+                for (rank, &index) in ranked.iter().enumerate() {
+                    weights[index] = (rank + 1) as f32;
+                }
+
+                weights
+            }
+        }
+    }
+
+    fn select_parent(&mut self, weights: &[f32]) -> usize {
+        match self.settings.selection {
+            SelectionMethod::Roulette | SelectionMethod::Rank => self.spin_roulette(weights),
+            SelectionMethod::Tournament(size) => {
+                let mut selected_index = self.random.gen_range(0..self.agents.len());
+                for _ in 1..size {
+                    let index = self.random.gen_range(0..self.agents.len());
+                    if self.agents[index].score > self.agents[selected_index].score {
+                        selected_index = index;
+                    }
+                }
+
+                selected_index
+            }
+        }
+    }
+
+    fn spin_roulette(&mut self, weights: &[f32]) -> usize {
+        let total: f32 = weights.iter().sum();
+        if total <= 0.0 {
+            return self.random.gen_range(0..weights.len());
+        }
+
+        let mut r = self.random.gen_range(0.0..total);
+        for (index, weight) in weights.iter().enumerate() {
+            if r < *weight {
+                return index;
+            }
+            r -= weight;
+        }
+
+        weights.len() - 1
+    }
+
+    fn sorted_by_score(&self) -> Vec<&Agent> {
+        let mut sorted: Vec<&Agent> = self.agents.iter().collect();
+        sorted.sort_by(|a, b| b.score.cmp(&a.score));
+        sorted
+    }
+
+    fn new_generation(&mut self) {
+        let pop_size = self.settings.pop_size;
+        let mut new_agents: Vec<Agent> = Vec::with_capacity(pop_size);
+
+        let elitism = self.settings.elitism.min(pop_size);
+        for elite in self.sorted_by_score().into_iter().take(elitism) {
+            new_agents.push(elite.clone());
+        }
+
+        let weights = self.selection_weights();
+        while new_agents.len() < pop_size {
+            let p1 = self.select_parent(&weights);
+            let p2 = self.select_parent(&weights);
+            let mut child = self.agents[p1].clone();
+            let alpha = self.random.gen_range(0.0..=1.0);
+
+            child.net.crossover(&self.agents[p2].net, alpha);
+            child.net.mutate(
+                &self.settings.weight_mutation,
+                &self.settings.bias_mutation,
+                &mut self.random,
+            );
+
+            new_agents.push(child);
+        }
+
+        self.agents = new_agents;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, process};
+
+    use super::*;
+
+    fn settings(selection: SelectionMethod) -> GeneticAlgorithmSettings {
+        GeneticAlgorithmSettings {
+            pop_size: 12,
+            seed: 7,
+            elitism: 1,
+            selection,
+            ..Default::default()
+        }
+    }
+
+    fn genes(net: &NeuralNetwork) -> Vec<f32> {
+        net.genes().collect()
+    }
+
+    fn fitness_on_threads(threads: usize) -> Vec<(i32, f32)> {
+        let mut settings = settings(SelectionMethod::Roulette);
+        settings.shaping = 0.5;
+        let mut genetic_algorithm = GeneticAlgorithm::new(settings);
+        genetic_algorithm.init_random();
+
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build()
+            .unwrap();
+        pool.install(|| genetic_algorithm.calculate_fitness());
+        genetic_algorithm
+            .agents
+            .iter()
+            .map(|agent| (agent.score, agent.fitness))
+            .collect()
+    }
+
+    #[test]
+    fn fitness_does_not_depend_on_thread_count() {
+        assert_eq!(fitness_on_threads(1), fitness_on_threads(4));
+    }
+
+    fn run(
+        genetic_algorithm: &mut GeneticAlgorithm,
+        generations: u32,
+    ) -> (Vec<f32>, Vec<serde_json::Value>) {
+        let mut stats = Vec::new();
+        let best = genetic_algorithm
+            .get_best_with_progress(generations, |generation| {
+                stats.push(serde_json::to_value(generation).unwrap())
+            })
+            .unwrap();
+        (genes(&best), stats)
+    }
+
+    #[test]
+    fn resuming_a_checkpoint_matches_an_uninterrupted_run() {
+        let path = env::temp_dir().join(format!("arche-resume-test-{}.json", process::id()));
+        let settings = settings(SelectionMethod::Tournament(3));
+
+        let (best, stats) = run(&mut GeneticAlgorithm::new(settings.clone()), 4);
+
+        let mut interrupted = GeneticAlgorithm::new(settings).with_checkpoints(&path, 2);
+        interrupted.get_best(2).unwrap();
+        let mut resumed = GeneticAlgorithm::resume(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(resumed.current_generation(), 1);
+
+        let (resumed_best, resumed_stats) = run(&mut resumed, 4);
+        assert_eq!(resumed_best, best);
+        assert_eq!(resumed_stats, stats[1..]);
+    }
+
+    #[test]
+    fn failed_checkpoints_are_reported() {
+        let path = env::temp_dir()
+            .join("arche-missing-directory")
+            .join("checkpoint.json");
+        let mut genetic_algorithm =
+            GeneticAlgorithm::new(settings(SelectionMethod::Roulette)).with_checkpoints(path, 1);
+        assert!(genetic_algorithm.get_best(2).is_err());
+    }
+
+    #[test]
+    fn roulette_with_zero_weights_picks_any_index() {
+        let mut genetic_algorithm = GeneticAlgorithm::new(settings(SelectionMethod::Roulette));
+        for _ in 0..100 {
+            assert!(genetic_algorithm.spin_roulette(&[0.0; 5]) < 5);
+        }
+    }
+
+    #[test]
+    fn roulette_never_picks_zero_weights() {
+        let mut genetic_algorithm = GeneticAlgorithm::new(settings(SelectionMethod::Roulette));
+        for _ in 0..100 {
+            assert_eq!(genetic_algorithm.spin_roulette(&[0.0, 2.0, 0.0]), 1);
+        }
+    }
+
+    #[test]
+    fn elitism_keeps_the_best_agent_unchanged() {
+        for selection in [
+            SelectionMethod::Roulette,
+            SelectionMethod::Tournament(3),
+            SelectionMethod::Rank,
+        ] {
+            let mut genetic_algorithm = GeneticAlgorithm::new(settings(selection));
+            genetic_algorithm.init_random();
+            genetic_algorithm.calculate_fitness();
+            let best = genes(&genetic_algorithm.best_agent().net);
+
+            genetic_algorithm.new_generation();
+            assert_eq!(genetic_algorithm.agents.len(), 12);
+            assert_eq!(genes(&genetic_algorithm.agents[0].net), best);
+        }
+    }
+
+    #[test]
+    fn rank_selection_handles_equal_scores() {
+        let mut genetic_algorithm = GeneticAlgorithm::new(settings(SelectionMethod::Rank));
+        genetic_algorithm.init_random();
+        for agent in genetic_algorithm.agents.iter_mut() {
+            agent.score = 0;
+        }
+
+        let weights = genetic_algorithm.selection_weights();
+        assert_eq!(weights.len(), 12);
+        for _ in 0..100 {
+            assert!(genetic_algorithm.select_parent(&weights) < 12);
+        }
+    }
+}
@@ -0,0 +1,119 @@
+use bevy::prelude::*;
+
+use super::{
+    collision::{ColliderShape, CollisionLayer, CollisionMask, RectCollider},
+    neural_network::HitZone,
+};
+
+pub const ARCHER_X: f32 = 12.0;
+pub const ARCHER_Z: f32 = 0.2;
+pub const CEILING: f32 = 12.0;
+pub const ARM_OFFSET: Vec2 = Vec2::new(0.3, 2.1);
+pub const BOW_REACH: f32 = 1.3;
+pub const SHOOTING_POINT_REACH: f32 = 1.25;
+pub const SHOOTING_REACH: f32 = BOW_REACH + SHOOTING_POINT_REACH;
+pub const ARROW_SHAPE: ColliderShape = ColliderShape::OrientedBox {
+    half_extends: Vec2::new(0.15, 0.15),
+};
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Hitbox {
+    pub zone: HitZone,
+    pub offset: f32,
+    pub shape: ColliderShape,
+}
+
+impl Hitbox {
+    pub const HEAD: Hitbox = Hitbox {
+        zone: HitZone::Head,
+        offset: 2.8,
+        shape: ColliderShape::Circle { radius: 0.35 },
+    };
+    pub const BODY: Hitbox = Hitbox {
+        zone: HitZone::Body,
+        offset: 1.9,
+        shape: ColliderShape::Aabb {
+            half_extends: Vec2::new(0.4, 0.5),
+        },
+    };
+    pub const LEGS: Hitbox = Hitbox {
+        zone: HitZone::Legs,
+        offset: 0.7,
+        shape: ColliderShape::Aabb {
+            half_extends: Vec2::new(0.4, 0.6),
+        },
+    };
+    pub const ALL: [Hitbox; 3] = [Hitbox::HEAD, Hitbox::BODY, Hitbox::LEGS];
+
+    pub fn of(zone: HitZone) -> Option<Hitbox> {
+        Hitbox::ALL.into_iter().find(|hitbox| hitbox.zone == zone)
+    }
+
+    pub fn transform(&self) -> Transform {
+        Transform::from_translation(Vec3::new(0.0, self.offset, 0.0))
+    }
+
+    pub fn collider(&self, owner: Option<Entity>, flipped: bool) -> RectCollider {
+        RectCollider::with_shape(owner, Vec2::ZERO, self.shape).with_layer(
+            hitbox_layer(flipped),
+            CollisionMask::NONE.with(CollisionLayer::Arrow),
+        )
+    }
+
+    pub fn center(&self, archer_position: Vec2) -> Vec2 {
+        archer_position + Vec2::new(0.0, self.offset)
+    }
+}
+
+pub fn hitbox_layer(flipped: bool) -> CollisionLayer {
+    if flipped {
+        CollisionLayer::EnemyHitbox
+    } else {
+        CollisionLayer::PlayerHitbox
+    }
+}
+
+pub fn arrow_collider(owner: Option<Entity>, flipped: bool) -> RectCollider {
+    let mask = CollisionMask::NONE
+        .with(hitbox_layer(!flipped))
+        .with(CollisionLayer::MenuButton)
+        .with(CollisionLayer::Terrain);
+    RectCollider::with_shape(owner, Vec2::ZERO, ARROW_SHAPE).with_layer(CollisionLayer::Arrow, mask)
+}
+
+pub fn tower_top(height: f32) -> f32 {
+    (height * 12.0) - (17.0 * 0.5) + 1.0
+}
+
+pub fn archer_position(flipped: bool, height: f32) -> Vec2 {
+    let x = if flipped { ARCHER_X } else { -ARCHER_X };
+    Vec2::new(x, tower_top(height))
+}
+
+pub fn archer_transform(flipped: bool) -> Transform {
+    let x = if flipped { ARCHER_X } else { -ARCHER_X };
+    let scale = if flipped { -1.0 } else { 1.0 };
+    Transform::from_translation(Vec3::new(x, 0.0, ARCHER_Z)).with_scale(Vec3::new(scale, 1.0, 1.0))
+}
+
+pub fn combat_transform() -> Transform {
+    Transform::from_translation(Vec3::new(0.0, 0.0, 0.2))
+}
+
+pub fn arm_transform() -> Transform {
+    Transform::from_translation(ARM_OFFSET.extend(-0.1))
+}
+
+pub fn bow_transform() -> Transform {
+    Transform::from_translation(Vec3::new(BOW_REACH, 0.0, 0.2))
+}
+
+pub fn shooting_point_transform() -> Transform {
+    Transform::from_translation(Vec3::new(SHOOTING_POINT_REACH, 0.0, 0.0))
+}
+
+pub fn launch_point(self_height: f32, angle: f32) -> Vec2 {
+    archer_position(false, self_height)
+        + ARM_OFFSET
+        + Vec2::new(f32::cos(angle), f32::sin(angle)) * SHOOTING_REACH
+}
@@ -0,0 +1,714 @@
+use std::str::FromStr;
+
+use rand::Rng;
+use rand_distr::StandardNormal;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    arrow::{Arrow, ARROW_POWER_SCALE, ARROW_STEP},
+    geometry::{self, Hitbox, ARCHER_X, CEILING},
+    observation::{Observation, ObservationFeature},
+    GameState, GameTurn,
+};
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum MutationNoise {
+    Uniform,
+    Gaussian,
+}
+
+impl FromStr for MutationNoise {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "uniform" => Ok(MutationNoise::Uniform),
+            "gaussian" => Ok(MutationNoise::Gaussian),
+            _ => Err(format!("Unknown mutation noise '{value}'")),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+pub struct MutationSettings {
+    pub probability: f32,
+    pub magnitude: f32,
+    pub noise: MutationNoise,
+}
+
+impl MutationSettings {
+    pub fn new(probability: f32, magnitude: f32, noise: MutationNoise) -> Self {
+        Self {
+            probability,
+            magnitude,
+            noise,
+        }
+    }
+
+    fn mutate_gene<R: Rng>(&self, gene: &mut f32, random: &mut R) {
+        if self.probability < 1.0 && random.gen::<f32>() >= self.probability {
+            return;
+        }
+
+        let noise: f32 = match self.noise {
+            MutationNoise::Uniform => random.gen_range(-1.0..=1.0),
+            MutationNoise::Gaussian => random.sample(StandardNormal),
+        };
+        *gene += noise * self.magnitude;
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum HitZone {
+    Head,
+    Body,
+    Legs,
+    Miss,
+}
+
+impl HitZone {
+    pub fn score(&self) -> i32 {
+        match self {
+            HitZone::Head => 100,
+            HitZone::Body => 50,
+            HitZone::Legs => 30,
+            HitZone::Miss => 0,
+        }
+    }
+
+    pub fn damage(&self) -> i32 {
+        match self {
+            HitZone::Head => 4,
+            HitZone::Body | HitZone::Legs => 2,
+            HitZone::Miss => 0,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Default, Debug)]
+pub struct HitCounts {
+    pub head: u32,
+    pub body: u32,
+    pub legs: u32,
+    pub miss: u32,
+}
+
+impl HitCounts {
+    pub fn add(&mut self, zone: HitZone) {
+        match zone {
+            HitZone::Head => self.head += 1,
+            HitZone::Body => self.body += 1,
+            HitZone::Legs => self.legs += 1,
+            HitZone::Miss => self.miss += 1,
+        }
+    }
+
+    pub fn merge(&mut self, other: &HitCounts) {
+        self.head += other.head;
+        self.body += other.body;
+        self.legs += other.legs;
+        self.miss += other.miss;
+    }
+}
+
+pub const OUTPUTS_COUNT: usize = 2;
+
+const SHAPING_RADIUS: f32 = 4.0;
+
+#[derive(Clone, Copy, Debug)]
+pub struct ShotTrace {
+    pub zone: HitZone,
+    pub head_distance: f32,
+    pub body_distance: f32,
+    pub legs_distance: f32,
+}
+
+impl ShotTrace {
+    pub fn near_miss_score(&self) -> f32 {
+        [
+            (HitZone::Head, self.head_distance),
+            (HitZone::Body, self.body_distance),
+            (HitZone::Legs, self.legs_distance),
+        ]
+        .iter()
+        .map(|(zone, distance)| {
+            zone.score() as f32 * (1.0 - distance / SHAPING_RADIUS).clamp(0.0, 1.0)
+        })
+        .fold(0.0, f32::max)
+    }
+
+    pub fn shaped_score(&self, shaping: f32) -> f32 {
+        (1.0 - shaping) * self.zone.score() as f32 + shaping * self.near_miss_score()
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Activation {
+    Sigmoid,
+    Tanh,
+    Relu,
+    Linear,
+}
+
+impl Activation {
+    pub fn apply(&self, net: f32) -> f32 {
+        match self {
+            Activation::Sigmoid => 1.0 / (1.0 + f32::exp(-net)),
+            Activation::Tanh => f32::tanh(net),
+            Activation::Relu => f32::max(net, 0.0),
+            Activation::Linear => net,
+        }
+    }
+}
+
+impl FromStr for Activation {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "sigmoid" => Ok(Activation::Sigmoid),
+            "tanh" => Ok(Activation::Tanh),
+            "relu" => Ok(Activation::Relu),
+            "linear" => Ok(Activation::Linear),
+            _ => Err(format!("Unknown activation '{value}'")),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct NetworkTopology {
+    #[serde(default = "ObservationFeature::defaults")]
+    inputs: Vec<ObservationFeature>,
+    layer_sizes: Vec<usize>,
+    activations: Vec<Activation>,
+}
+
+impl NetworkTopology {
+    pub fn new(
+        inputs: &[ObservationFeature],
+        hidden_sizes: &[usize],
+        activations: &[Activation],
+    ) -> Result<Self, String> {
+        let mut layer_sizes = vec![inputs.len()];
+        layer_sizes.extend_from_slice(hidden_sizes);
+        layer_sizes.push(OUTPUTS_COUNT);
+
+        if layer_sizes.iter().any(|&size| size == 0) {
+            return Err("Every layer needs at least one neuron".into());
+        }
+
+        let activations = if activations.is_empty() {
+            vec![Activation::Sigmoid; layer_sizes.len() - 1]
+        } else {
+            activations.to_vec()
+        };
+
+        if activations.len() != layer_sizes.len() - 1 {
+            return Err(format!(
+                "Expected {} activations, one for every hidden and output layer, got {}",
+                layer_sizes.len() - 1,
+                activations.len()
+            ));
+        }
+
+        Ok(Self {
+            inputs: inputs.to_vec(),
+            layer_sizes,
+            activations,
+        })
+    }
+
+    pub fn inputs(&self) -> &[ObservationFeature] {
+        &self.inputs
+    }
+
+    pub fn layer_sizes(&self) -> &[usize] {
+        &self.layer_sizes
+    }
+
+    pub fn activations(&self) -> &[Activation] {
+        &self.activations
+    }
+}
+
+impl Default for NetworkTopology {
+    fn default() -> Self {
+        NetworkTopology::new(&ObservationFeature::DEFAULT, &[3], &[]).unwrap()
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum LayerInput {
+    #[default]
+    NetworkInput, // Nets saved before layers were chained feed the raw input to every layer.
+    PreviousLayer,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct NeuralNetwork {
+    weights: Vec<Vec<Vec<f32>>>,
+    biases: Vec<Vec<f32>>,
+    #[serde(default)]
+    activations: Vec<Activation>,
+    #[serde(default)]
+    layer_input: LayerInput,
+    #[serde(default = "ObservationFeature::defaults")]
+    inputs: Vec<ObservationFeature>,
+}
+
+impl NeuralNetwork {
+    pub fn new<R: Rng>(topology: &NetworkTopology, random: &mut R) -> Self {
+        let mut weights = Vec::with_capacity(topology.layer_sizes.len() - 1);
+        let mut biases = Vec::with_capacity(topology.layer_sizes.len() - 1);
+        for sizes in topology.layer_sizes.windows(2) {
+            let (inputs, neurons) = (sizes[0], sizes[1]);
+            weights.push(
+                (0..neurons)
+                    .map(|_| (0..inputs).map(|_| random.gen_range(-1.0..=1.0)).collect())
+                    .collect(),
+            );
+            biases.push((0..neurons).map(|_| random.gen_range(-1.0..=1.0)).collect());
+        }
+
+        Self {
+            weights,
+            biases,
+            activations: topology.activations.clone(),
+            layer_input: LayerInput::PreviousLayer,
+            inputs: topology.inputs.clone(),
+        }
+    }
+
+    fn get_weight(&self, layer: usize, neuron: usize, input: usize) -> f32 {
+        self.weights[layer][neuron][input]
+    }
+
+    fn get_bias(&self, layer: usize, neuron: usize) -> f32 {
+        self.biases[layer][neuron]
+    }
+
+    fn layers_count(&self) -> usize {
+        self.biases.len()
+    }
+
+    fn neurons_count(&self, layer: usize) -> usize {
+        self.biases[layer].len()
+    }
+
+    fn connections_count(&self, layer: usize, neuron: usize) -> usize {
+        self.weights[layer][neuron].len()
+    }
+
+    fn activation(&self, layer: usize) -> Activation {
+        self.activations
+            .get(layer)
+            .copied()
+            .unwrap_or(Activation::Sigmoid)
+    }
+
+    pub fn inputs_count(&self) -> usize {
+        self.weights
+            .first()
+            .and_then(|layer| layer.first())
+            .map_or(0, |neuron| neuron.len())
+    }
+
+    pub fn inputs(&self) -> &[ObservationFeature] {
+        &self.inputs
+    }
+
+    pub fn outputs_count(&self) -> usize {
+        self.biases.last().map_or(0, |layer| layer.len())
+    }
+
+    pub fn layer_sizes(&self) -> Vec<usize> {
+        let mut layer_sizes = vec![self.inputs_count()];
+        layer_sizes.extend(self.biases.iter().map(|layer| layer.len()));
+        layer_sizes
+    }
+
+    pub fn activations(&self) -> Vec<Activation> {
+        (0..self.layers_count())
+            .map(|n_layer| self.activation(n_layer))
+            .collect()
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.layers_count() == 0 || self.weights.len() != self.layers_count() {
+            return Err(format!(
+                "Expected matching weight and bias layers, got {} and {}",
+                self.weights.len(),
+                self.layers_count()
+            ));
+        }
+
+        if self.inputs.len() != self.inputs_count() {
+            return Err(format!(
+                "Expected {} inputs for the observed features {:?}, got {}",
+                self.inputs.len(),
+                self.inputs,
+                self.inputs_count()
+            ));
+        }
+
+        if !self.activations.is_empty() && self.activations.len() != self.layers_count() {
+            return Err(format!(
+                "Expected {} activations, got {}",
+                self.layers_count(),
+                self.activations.len()
+            ));
+        }
+
+        for n_layer in 0..self.layers_count() {
+            if self.weights[n_layer].len() != self.neurons_count(n_layer) {
+                return Err(format!(
+                    "Layer {n_layer} has {} weight rows for {} neurons",
+                    self.weights[n_layer].len(),
+                    self.neurons_count(n_layer)
+                ));
+            }
+
+            let expected_connections = match self.layer_input {
+                _ if n_layer == 0 => self.inputs_count(),
+                LayerInput::NetworkInput => self.inputs_count(),
+                LayerInput::PreviousLayer => self.neurons_count(n_layer - 1),
+            };
+            for n_neuron in 0..self.neurons_count(n_layer) {
+                if self.connections_count(n_layer, n_neuron) != expected_connections {
+                    return Err(format!(
+                        "Neuron {n_neuron} of layer {n_layer} has {} connections, expected {}",
+                        self.connections_count(n_layer, n_neuron),
+                        expected_connections
+                    ));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn calculate_output(&self, input: Vec<f32>) -> Vec<f32> {
+        let mut neurons: Vec<f32> = input.clone();
+
+        for n_layer in 0..self.layers_count() {
+            let layer_input = match self.layer_input {
+                LayerInput::NetworkInput => &input,
+                LayerInput::PreviousLayer => &neurons,
+            };
+
+            let mut layer_output = Vec::with_capacity(self.neurons_count(n_layer));
+            for n_neuron in 0..self.neurons_count(n_layer) {
+                let mut net = self.get_bias(n_layer, n_neuron);
+                for n_connection in 0..self.connections_count(n_layer, n_neuron) {
+                    net += layer_input[n_connection]
+                        * self.get_weight(n_layer, n_neuron, n_connection);
+                }
+
+                layer_output.push(self.activation(n_layer).apply(net));
+            }
+
+            neurons = layer_output;
+        }
+
+        neurons
+    }
+
+    pub fn observe(&self, observation: &Observation) -> Vec<f32> {
+        self.calculate_output(observation.features(&self.inputs))
+    }
+
+    pub fn mutate<R: Rng>(
+        &mut self,
+        weight_mutation: &MutationSettings,
+        bias_mutation: &MutationSettings,
+        random: &mut R,
+    ) {
+        for layer in self.weights.iter_mut() {
+            for neuron in layer.iter_mut() {
+                for weight in neuron.iter_mut() {
+                    weight_mutation.mutate_gene(weight, random);
+                }
+            }
+        }
+
+        for layer in self.biases.iter_mut() {
+            for bias in layer.iter_mut() {
+                bias_mutation.mutate_gene(bias, random);
+            }
+        }
+    }
+
+    pub fn crossover(&mut self, other: &NeuralNetwork, alpha: f32) {
+        for n_layer in 0..self.layers_count() {
+            for n_neuron in 0..self.neurons_count(n_layer) {
+                let self_bias = self.get_bias(n_layer, n_neuron);
+                let other_bias = other.get_bias(n_layer, n_neuron);
+                self.biases[n_layer][n_neuron] = ((1.0 - alpha) * self_bias) + (alpha * other_bias);
+
+                for n_connection in 0..self.connections_count(n_layer, n_neuron) {
+                    let self_weight = self.get_weight(n_layer, n_neuron, n_connection);
+                    let other_weight = other.get_weight(n_layer, n_neuron, n_connection);
+                    let average = ((1.0 - alpha) * self_weight) + (alpha * other_weight);
+                    self.weights[n_layer][n_neuron][n_connection] = average;
+                }
+            }
+        }
+    }
+
+    pub fn max_score() -> i32 {
+        11 * 11 * HitZone::Head.score()
+    }
+
+    pub fn score(&self) -> i32 {
+        self.score_with_hits().0
+    }
+
+    pub fn score_with_hits(&self) -> (i32, HitCounts) {
+        self.shaped_score_with_hits(0.0)
+    }
+
+    pub fn shaped_score_with_hits(&self, shaping: f32) -> (i32, HitCounts) {
+        let exchanges = self
+            .inputs
+            .iter()
+            .any(|feature| !ObservationFeature::DEFAULT.contains(feature));
+
+        let mut score = 0.0;
+        let mut hits = HitCounts::default();
+        let mut game_state = GameState::new();
+        for i in 0..=10 {
+            let h_self = i as f32 * 0.1;
+            for j in 0..=10 {
+                let h_other = j as f32 * 0.1;
+                game_state.enemy_height = h_self;
+                game_state.player_height = h_other;
+
+                if exchanges {
+                    let output = self.observe_turn(&mut game_state, GameTurn::Player);
+                    let zone = NeuralNetwork::simulate_shot(output[0], output[1], h_other, h_self);
+                    NeuralNetwork::exchange_shot(&mut game_state, GameTurn::Player, &output, zone);
+                }
+
+                let output = self.observe_turn(&mut game_state, GameTurn::Enemy);
+                let trace = NeuralNetwork::trace_shot(output[0], output[1], h_self, h_other);
+                score += trace.shaped_score(shaping);
+                hits.add(trace.zone);
+
+                if exchanges {
+                    NeuralNetwork::exchange_shot(
+                        &mut game_state,
+                        GameTurn::Enemy,
+                        &output,
+                        trace.zone,
+                    );
+                }
+            }
+        }
+
+        (score.round() as i32, hits)
+    }
+
+    fn observe_turn(&self, game_state: &mut GameState, shooter: GameTurn) -> Vec<f32> {
+        game_state.turn_count = game_state.shot_history.len() as i32;
+        self.observe(&Observation::new(game_state, shooter))
+    }
+
+    fn exchange_shot(game_state: &mut GameState, shooter: GameTurn, output: &[f32], zone: HitZone) {
+        if game_state.apply_shot(shooter, output[0], output[1], zone) {
+            let heights = (game_state.player_height, game_state.enemy_height);
+            *game_state = GameState::new();
+            (game_state.player_height, game_state.enemy_height) = heights;
+        }
+    }
+
+    pub fn genes(&self) -> impl Iterator<Item = f32> + '_ {
+        let weights = self.weights.iter().flatten().flatten();
+        let biases = self.biases.iter().flatten();
+        weights.chain(biases).copied()
+    }
+
+    pub fn eval_shot(power: f32, angle: f32, self_height: f32, enemy_height: f32) -> i32 {
+        NeuralNetwork::simulate_shot(power, angle, self_height, enemy_height).score()
+    }
+
+    pub fn simulate_shot(power: f32, angle: f32, self_height: f32, enemy_height: f32) -> HitZone {
+        NeuralNetwork::trace_shot(power, angle, self_height, enemy_height).zone
+    }
+
+    pub fn trace_shot(power: f32, angle: f32, self_height: f32, enemy_height: f32) -> ShotTrace {
+        let shoot_pos = geometry::launch_point(self_height, angle);
+
+        let mut arrow_col = geometry::arrow_collider(None, false);
+        arrow_col.set_center(shoot_pos);
+
+        let enemy_pos = geometry::archer_position(true, enemy_height);
+        let [enemy_head_col, enemy_body_col, enemy_legs_col] = Hitbox::ALL.map(|hitbox| {
+            let mut collider = hitbox.collider(None, true);
+            collider.set_center(hitbox.center(enemy_pos));
+            collider
+        });
+
+        let mut trace = ShotTrace {
+            zone: HitZone::Miss,
+            head_distance: f32::MAX,
+            body_distance: f32::MAX,
+            legs_distance: f32::MAX,
+        };
+
+        let mut previous_pos = shoot_pos;
+        let mut t = 0.0;
+        loop {
+            let arrow_pos = Arrow::get_trajectory(power * ARROW_POWER_SCALE, angle, t);
+            let arrow_col_pos = shoot_pos + arrow_pos;
+            let diff = arrow_col_pos - previous_pos;
+            arrow_col.set_center(arrow_col_pos);
+            arrow_col.set_rotation(f32::atan2(diff.y, diff.x));
+
+            trace.head_distance = trace
+                .head_distance
+                .min(arrow_col.aabb_distance_to(&enemy_head_col));
+            trace.body_distance = trace
+                .body_distance
+                .min(arrow_col.aabb_distance_to(&enemy_body_col));
+            trace.legs_distance = trace
+                .legs_distance
+                .min(arrow_col.aabb_distance_to(&enemy_legs_col));
+
+            let hitboxes = [
+                (HitZone::Head, &enemy_head_col),
+                (HitZone::Body, &enemy_body_col),
+                (HitZone::Legs, &enemy_legs_col),
+            ];
+            if let Some((zone, _)) = arrow_col.sweep_first(previous_pos, arrow_col_pos, hitboxes) {
+                trace.zone = zone;
+                return trace;
+            }
+
+            if arrow_col_pos.x > ARCHER_X || arrow_col_pos.y > CEILING || arrow_col_pos.y < -CEILING
+            {
+                return trace;
+            }
+
+            previous_pos = arrow_col_pos;
+            t += ARROW_STEP;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    use super::*;
+    use crate::game::ballistics;
+
+    fn network(hidden_sizes: &[usize]) -> NeuralNetwork {
+        let topology =
+            NetworkTopology::new(&ObservationFeature::DEFAULT, hidden_sizes, &[]).unwrap();
+        NeuralNetwork::new(&topology, &mut ChaCha8Rng::seed_from_u64(3))
+    }
+
+    #[test]
+    fn mutation_changes_biases() {
+        let mut net = network(&[3]);
+        let (weights, biases) = (net.weights.clone(), net.biases.clone());
+        let frozen = MutationSettings::new(0.0, 0.5, MutationNoise::Uniform);
+        let always = MutationSettings::new(1.0, 0.5, MutationNoise::Gaussian);
+
+        net.mutate(&frozen, &always, &mut ChaCha8Rng::seed_from_u64(5));
+        assert_eq!(net.weights, weights);
+        for (mutated, original) in net.biases.iter().flatten().zip(biases.iter().flatten()) {
+            assert_ne!(mutated, original);
+        }
+    }
+
+    #[test]
+    fn zero_probability_leaves_every_gene_untouched() {
+        let mut net = network(&[4, 3]);
+        let genes: Vec<f32> = net.genes().collect();
+        let frozen = MutationSettings::new(0.0, 10.0, MutationNoise::Uniform);
+
+        net.mutate(&frozen, &frozen, &mut ChaCha8Rng::seed_from_u64(5));
+        assert_eq!(net.genes().collect::<Vec<f32>>(), genes);
+    }
+
+    #[test]
+    fn layers_feed_the_previous_layer() {
+        let net = NeuralNetwork {
+            weights: vec![
+                vec![
+                    vec![1.0, 0.0],
+                    vec![0.0, 1.0],
+                    vec![1.0, 1.0],
+                    vec![1.0, -1.0],
+                ],
+                vec![
+                    vec![1.0, 0.0, 0.0, 0.0],
+                    vec![0.0, 1.0, 1.0, 0.0],
+                    vec![0.0, 0.0, 0.0, 1.0],
+                ],
+                vec![vec![1.0, 1.0, 0.0], vec![0.0, 0.0, 4.0]],
+            ],
+            biases: vec![vec![0.0; 4], vec![0.0, 0.5, 0.0], vec![0.0, -0.5]],
+            activations: vec![Activation::Relu; 3],
+            layer_input: LayerInput::PreviousLayer,
+            inputs: ObservationFeature::defaults(),
+        };
+        assert_eq!(net.validate(), Ok(()));
+        assert_eq!(net.layer_sizes(), vec![2, 4, 3, 2]);
+
+        // [0.5, 0.25] -> [0.5, 0.25, 0.75, 0.25] -> [0.5, 1.5, 0.25] -> [2.0, 0.5]
+        assert_eq!(net.calculate_output(vec![0.5, 0.25]), vec![2.0, 0.5]);
+    }
+
+    #[test]
+    fn sized_topologies_build_matching_layers() {
+        let net = network(&[4, 3]);
+        assert_eq!(net.validate(), Ok(()));
+        assert_eq!(net.layer_sizes(), vec![2, 4, 3, 2]);
+        assert_eq!(net.calculate_output(vec![0.5, 0.25]).len(), 2);
+    }
+
+    #[test]
+    fn zero_shaping_keeps_the_plain_hit_score() {
+        let net = network(&[4]);
+        let mut plain = 0;
+        for i in 0..=10 {
+            for j in 0..=10 {
+                let (h_self, h_other) = (i as f32 * 0.1, j as f32 * 0.1);
+                let output = net.observe(&Observation::new(
+                    &GameState {
+                        enemy_height: h_self,
+                        player_height: h_other,
+                        ..GameState::new()
+                    },
+                    GameTurn::Enemy,
+                ));
+                plain += NeuralNetwork::eval_shot(output[0], output[1], h_self, h_other);
+            }
+        }
+        assert_eq!(net.shaped_score_with_hits(0.0).0, plain);
+
+        let trace = ShotTrace {
+            zone: HitZone::Miss,
+            head_distance: 0.1,
+            body_distance: 0.1,
+            legs_distance: 0.1,
+        };
+        assert_eq!(trace.shaped_score(0.0), 0.0);
+    }
+
+    #[test]
+    fn near_misses_score_more_than_far_misses() {
+        let aim = ballistics::aim(0.5, 0.5).unwrap();
+        let near = NeuralNetwork::trace_shot(aim.power * 0.9, aim.angle, 0.5, 0.5);
+        let far = NeuralNetwork::trace_shot(aim.power * 0.6, aim.angle, 0.5, 0.5);
+        assert_eq!(near.zone, HitZone::Miss);
+        assert_eq!(far.zone, HitZone::Miss);
+
+        assert!(near.near_miss_score() > far.near_miss_score());
+        assert!(near.shaped_score(0.5) > far.shaped_score(0.5));
+        assert_eq!(near.shaped_score(0.0), far.shaped_score(0.0));
+    }
+}
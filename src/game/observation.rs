@@ -0,0 +1,109 @@
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use super::{GameState, GameTurn, MAX_HEALTH};
+
+const TURN_SCALE: f32 = 20.0;
+const OFFSET_SCALE: f32 = 10.0;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ObservationFeature {
+    SelfHeight,
+    EnemyHeight,
+    SelfHealth,
+    EnemyHealth,
+    Turn,
+    LastOffset,
+}
+
+impl ObservationFeature {
+    pub const DEFAULT: [ObservationFeature; 2] = [
+        ObservationFeature::SelfHeight,
+        ObservationFeature::EnemyHeight,
+    ];
+
+    pub fn defaults() -> Vec<ObservationFeature> {
+        ObservationFeature::DEFAULT.to_vec()
+    }
+}
+
+impl FromStr for ObservationFeature {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "self_height" => Ok(ObservationFeature::SelfHeight),
+            "enemy_height" => Ok(ObservationFeature::EnemyHeight),
+            "self_health" => Ok(ObservationFeature::SelfHealth),
+            "enemy_health" => Ok(ObservationFeature::EnemyHealth),
+            "turn" => Ok(ObservationFeature::Turn),
+            "last_offset" => Ok(ObservationFeature::LastOffset),
+            _ => Err(format!("Unknown observation feature '{value}'")),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Observation {
+    pub self_height: f32,
+    pub enemy_height: f32,
+    pub self_health: i32,
+    pub enemy_health: i32,
+    pub turn: i32,
+    pub last_offset: Option<f32>,
+}
+
+impl Observation {
+    pub fn new(game_state: &GameState, shooter: GameTurn) -> Self {
+        let (self_height, enemy_height, self_health, enemy_health) = match shooter {
+            GameTurn::Player => (
+                game_state.player_height,
+                game_state.enemy_height,
+                game_state.player_health,
+                game_state.enemy_health,
+            ),
+            GameTurn::Enemy => (
+                game_state.enemy_height,
+                game_state.player_height,
+                game_state.enemy_health,
+                game_state.player_health,
+            ),
+        };
+        let last_offset = game_state
+            .shot_history
+            .iter()
+            .rev()
+            .find(|shot| shot.shooter == shooter)
+            .map(|shot| shot.offset);
+
+        Self {
+            self_height,
+            enemy_height,
+            self_health,
+            enemy_health,
+            turn: game_state.turn_count,
+            last_offset,
+        }
+    }
+
+    pub fn value(&self, feature: ObservationFeature) -> f32 {
+        match feature {
+            ObservationFeature::SelfHeight => self.self_height,
+            ObservationFeature::EnemyHeight => self.enemy_height,
+            ObservationFeature::SelfHealth => self.self_health as f32 / MAX_HEALTH as f32,
+            ObservationFeature::EnemyHealth => self.enemy_health as f32 / MAX_HEALTH as f32,
+            ObservationFeature::Turn => (self.turn.max(0) as f32 / TURN_SCALE).min(1.0),
+            ObservationFeature::LastOffset => self
+                .last_offset
+                .map_or(0.0, |offset| (offset / OFFSET_SCALE).clamp(-1.0, 1.0)),
+        }
+    }
+
+    pub fn features(&self, features: &[ObservationFeature]) -> Vec<f32> {
+        features
+            .iter()
+            .map(|feature| self.value(*feature))
+            .collect()
+    }
+}
@@ -0,0 +1,242 @@
+use std::{f32::consts::FRAC_PI_4, str::FromStr};
+
+use bevy::math::Vec2;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use super::{
+    ballistics::{self, BallisticSolver, MAX_ANGLE, MAX_POWER, MIN_ANGLE, MIN_POWER},
+    neural_network::{HitZone, NeuralNetwork},
+    observation::Observation,
+};
+
+const BRACKET_HIT_TOLERANCE: f32 = 0.35;
+const BRACKET_MIN_WIDTH: f32 = 0.01;
+const BRACKET_REOPEN_WIDTH: f32 = 0.15;
+const BRACKET_MAX_CORRECTION: f32 = 0.35;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Shot {
+    pub power: f32,
+    pub angle: f32,
+}
+
+pub trait Strategy: Send + Sync {
+    fn shoot(&mut self, observation: &Observation) -> Option<Shot>;
+
+    fn ready(&self) -> bool {
+        true
+    }
+
+    fn set_network(&mut self, _net: &NeuralNetwork) {}
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum StrategyKind {
+    #[default]
+    Network,
+    Solver,
+    Random,
+    Bracketing,
+}
+
+impl StrategyKind {
+    pub fn build(&self, net: Option<&NeuralNetwork>, seed: u64) -> Box<dyn Strategy> {
+        match self {
+            StrategyKind::Network => Box::new(NetworkStrategy::new(net.cloned())),
+            StrategyKind::Solver => Box::new(SolverStrategy),
+            StrategyKind::Random => Box::new(RandomStrategy::new(seed)),
+            StrategyKind::Bracketing => Box::new(BracketingStrategy::new()),
+        }
+    }
+}
+
+impl FromStr for StrategyKind {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "network" => Ok(StrategyKind::Network),
+            "solver" => Ok(StrategyKind::Solver),
+            "random" => Ok(StrategyKind::Random),
+            "bracketing" => Ok(StrategyKind::Bracketing),
+            _ => Err(format!("Unknown AI strategy '{value}'")),
+        }
+    }
+}
+
+pub struct NetworkStrategy {
+    net: Option<NeuralNetwork>,
+}
+
+impl NetworkStrategy {
+    pub fn new(net: Option<NeuralNetwork>) -> Self {
+        Self { net }
+    }
+}
+
+impl Strategy for NetworkStrategy {
+    fn shoot(&mut self, observation: &Observation) -> Option<Shot> {
+        let net = self.net.as_ref()?;
+        let output = net.observe(observation);
+        Some(Shot {
+            power: output[0],
+            angle: output[1],
+        })
+    }
+
+    fn ready(&self) -> bool {
+        self.net.is_some()
+    }
+
+    fn set_network(&mut self, net: &NeuralNetwork) {
+        self.net = Some(net.clone());
+    }
+}
+
+pub struct SolverStrategy;
+
+impl Strategy for SolverStrategy {
+    fn shoot(&mut self, observation: &Observation) -> Option<Shot> {
+        ballistics::aim(observation.self_height, observation.enemy_height).map(|solution| Shot {
+            power: solution.power,
+            angle: solution.angle,
+        })
+    }
+}
+
+pub struct RandomStrategy {
+    random: StdRng,
+}
+
+impl RandomStrategy {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            random: StdRng::seed_from_u64(seed),
+        }
+    }
+}
+
+impl Strategy for RandomStrategy {
+    fn shoot(&mut self, _observation: &Observation) -> Option<Shot> {
+        Some(Shot {
+            power: self.random.gen_range(MIN_POWER..=MAX_POWER),
+            angle: self.random.gen_range(MIN_ANGLE..=MAX_ANGLE),
+        })
+    }
+}
+
+pub struct BracketingStrategy {
+    low: f32,
+    high: f32,
+    last_correction: Option<f32>,
+}
+
+impl BracketingStrategy {
+    pub fn new() -> Self {
+        Self {
+            low: -BRACKET_MAX_CORRECTION,
+            high: BRACKET_MAX_CORRECTION,
+            last_correction: None,
+        }
+    }
+
+    // Aims as if the enemy stood level with the bow, leaving the height difference to the correction.
+    pub fn estimate(observation: &Observation) -> Shot {
+        let solver = BallisticSolver::for_archer(observation.self_height);
+        let launch_height = solver.launch_point(FRAC_PI_4).y;
+        ballistics::target_point(observation.enemy_height, HitZone::Head)
+            .and_then(|target| solver.required_power(Vec2::new(target.x, launch_height), FRAC_PI_4))
+            .map(|power| Shot {
+                power,
+                angle: FRAC_PI_4,
+            })
+            .unwrap_or(Shot {
+                power: (MIN_POWER + MAX_POWER) * 0.5,
+                angle: FRAC_PI_4,
+            })
+    }
+
+    fn reopen(&mut self, correction: f32) {
+        self.low = correction - BRACKET_REOPEN_WIDTH;
+        self.high = correction + BRACKET_REOPEN_WIDTH;
+    }
+}
+
+impl Default for BracketingStrategy {
+    fn default() -> Self {
+        BracketingStrategy::new()
+    }
+}
+
+impl Strategy for BracketingStrategy {
+    fn shoot(&mut self, observation: &Observation) -> Option<Shot> {
+        if let (Some(correction), Some(offset)) = (self.last_correction, observation.last_offset) {
+            if offset.abs() < BRACKET_HIT_TOLERANCE {
+                self.reopen(correction);
+            } else if offset < 0.0 {
+                self.low = correction;
+            } else {
+                self.high = correction;
+            }
+
+            if self.high - self.low < BRACKET_MIN_WIDTH {
+                self.reopen((self.low + self.high) * 0.5);
+            }
+        }
+
+        let estimate = BracketingStrategy::estimate(observation);
+        let correction = (self.low + self.high) * 0.5;
+        self.last_correction = Some(correction);
+        Some(Shot {
+            power: (estimate.power + correction).clamp(MIN_POWER, MAX_POWER),
+            angle: estimate.angle,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::MAX_HEALTH;
+
+    const SELF_HEIGHT: f32 = 0.2;
+    const ENEMY_HEIGHT: f32 = 0.8;
+
+    fn observation(last_offset: Option<f32>) -> Observation {
+        Observation {
+            self_height: SELF_HEIGHT,
+            enemy_height: ENEMY_HEIGHT,
+            self_health: MAX_HEALTH,
+            enemy_health: MAX_HEALTH,
+            turn: 0,
+            last_offset,
+        }
+    }
+
+    #[test]
+    fn bracketing_closes_in_over_consecutive_turns() {
+        let mut strategy = BracketingStrategy::new();
+        let mut last_offset = None;
+        let mut misses = Vec::new();
+        for _ in 0..8 {
+            let shot = strategy.shoot(&observation(last_offset)).unwrap();
+            let offset = ballistics::shot_offset(shot.power, shot.angle, SELF_HEIGHT, ENEMY_HEIGHT);
+            misses.push(offset.abs());
+            last_offset = Some(offset);
+        }
+
+        assert!(misses[0] > BRACKET_HIT_TOLERANCE);
+        assert!(misses[4..].iter().all(|&miss| miss < BRACKET_HIT_TOLERANCE));
+        assert!(misses[7] < misses[0] * 0.1);
+    }
+
+    #[test]
+    fn estimate_ignores_the_height_difference() {
+        let level = BracketingStrategy::estimate(&Observation {
+            enemy_height: SELF_HEIGHT,
+            ..observation(None)
+        });
+        let higher = BracketingStrategy::estimate(&observation(None));
+        assert!((level.power - higher.power).abs() < 0.05);
+    }
+}
@@ -1,8 +1,6 @@
-use crate::game::GamePlugin;
+use arche::game::GamePlugin;
 use bevy::prelude::*;
 
-mod game;
-
 const BACKGROUND_COLOR: Color = Color::rgb(0.5, 0.5, 0.5);
 
 fn main() {
@@ -19,6 +17,10 @@ fn main() {
                     },
                     ..Default::default()
                 })
+                .set(AssetPlugin {
+                    watch_for_changes: true,
+                    ..Default::default()
+                })
                 .set(ImagePlugin::default_nearest()),
         )
         .add_plugin(GamePlugin)